@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use tiny_keccak::{Hasher, Keccak};
+
+/// Signatures of functions which are common enough to be decoded without fetching the contract's ABI.
+const KNOWN_FUNCTIONS: &[&str] = &[
+    // ERC20
+    "transfer(address to, uint256 value)",
+    "transferFrom(address from, address to, uint256 value)",
+    "approve(address spender, uint256 value)",
+    "increaseAllowance(address spender, uint256 addedValue)",
+    "decreaseAllowance(address spender, uint256 subtractedValue)",
+    // ERC721
+    "safeTransferFrom(address from, address to, uint256 tokenId)",
+    "safeTransferFrom(address from, address to, uint256 tokenId, bytes data)",
+    "setApprovalForAll(address operator, bool approved)",
+    // ERC1155
+    "safeTransferFrom(address from, address to, uint256 id, uint256 value, bytes data)",
+    "safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] values, bytes data)",
+    // WETH
+    "deposit()",
+    "withdraw(uint256 wad)",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum AbiError {
+    InvalidAbi(String),
+    InvalidType(String),
+    InvalidData(String),
+    UnknownSelector(String),
+}
+
+impl fmt::Display for AbiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiError::InvalidAbi(msg) => write!(f, "invalid ABI: {}", msg),
+            AbiError::InvalidType(kind) => write!(f, "unsupported ABI type '{}'", kind),
+            AbiError::InvalidData(msg) => write!(f, "invalid ABI encoded data: {}", msg),
+            AbiError::UnknownSelector(selector) => write!(f, "no ABI entry matches selector {}", selector),
+        }
+    }
+}
+
+impl std::error::Error for AbiError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamType {
+    Address,
+    Uint(usize),
+    Int(usize),
+    Bool,
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    FixedArray(Box<ParamType>, usize),
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    /// Parses a canonical type such as `uint256[]`. Tuples need their components and are built by `Param`.
+    pub fn parse(kind: &str) -> Result<ParamType, AbiError> {
+        ParamType::parse_with_components(kind, &[])
+    }
+
+    fn parse_with_components(kind: &str, components: &[Param]) -> Result<ParamType, AbiError> {
+        let invalid = || AbiError::InvalidType(kind.into());
+        if let Some(inner) = kind.strip_suffix(']') {
+            let open = inner.rfind('[').ok_or_else(invalid)?;
+            let element = Box::new(ParamType::parse_with_components(&inner[..open], components)?);
+            let size = &inner[open + 1..];
+            if size.is_empty() {
+                return Ok(ParamType::Array(element));
+            }
+            return Ok(ParamType::FixedArray(element, size.parse().map_err(|_| invalid())?));
+        }
+        let bits = |prefix: &str| -> Result<usize, AbiError> {
+            let size = &kind[prefix.len()..];
+            if size.is_empty() {
+                return Ok(256);
+            }
+            size.parse().map_err(|_| invalid())
+        };
+        match kind {
+            "address" => Ok(ParamType::Address),
+            "bool" => Ok(ParamType::Bool),
+            "string" => Ok(ParamType::String),
+            "bytes" => Ok(ParamType::Bytes),
+            "tuple" => Ok(ParamType::Tuple(components.iter().map(|c| c.kind.clone()).collect())),
+            _ if kind.starts_with("uint") => Ok(ParamType::Uint(bits("uint")?)),
+            _ if kind.starts_with("int") => Ok(ParamType::Int(bits("int")?)),
+            _ if kind.starts_with("bytes") => Ok(ParamType::FixedBytes(kind[5..].parse().map_err(|_| invalid())?)),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(element, _) => element.is_dynamic(),
+            ParamType::Tuple(elements) => elements.iter().any(|e| e.is_dynamic()),
+            _ => false,
+        }
+    }
+
+    /// The number of bytes the type occupies in the head of an encoding.
+    fn head_size(&self) -> usize {
+        if self.is_dynamic() {
+            return 32;
+        }
+        match self {
+            ParamType::FixedArray(element, size) => element.head_size() * size,
+            ParamType::Tuple(elements) => elements.iter().map(|e| e.head_size()).sum(),
+            _ => 32,
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamType::Address => write!(f, "address"),
+            ParamType::Uint(bits) => write!(f, "uint{}", bits),
+            ParamType::Int(bits) => write!(f, "int{}", bits),
+            ParamType::Bool => write!(f, "bool"),
+            ParamType::FixedBytes(size) => write!(f, "bytes{}", size),
+            ParamType::Bytes => write!(f, "bytes"),
+            ParamType::String => write!(f, "string"),
+            ParamType::Array(element) => write!(f, "{}[]", element),
+            ParamType::FixedArray(element, size) => write!(f, "{}[{}]", element, size),
+            ParamType::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "({})", elements.join(","))
+            }
+        }
+    }
+}
+
+/// A decoded value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Address(String),
+    Uint(U256),
+    /// Two's complement representation of a signed integer.
+    Int(U256),
+    Bool(bool),
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Token>),
+    FixedArray(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl Token {
+    pub fn as_address(&self) -> Option<&str> {
+        match self {
+            Token::Address(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn as_uint(&self) -> Option<U256> {
+        match self {
+            Token::Uint(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Token::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Address(address) => write!(f, "{}", address),
+            Token::Uint(value) => write!(f, "{}", value),
+            Token::Int(value) => {
+                if value.bit(255) {
+                    write!(f, "-{}", (!*value).overflowing_add(U256::one()).0)
+                } else {
+                    write!(f, "{}", value)
+                }
+            }
+            Token::Bool(value) => write!(f, "{}", value),
+            Token::FixedBytes(bytes) | Token::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+            Token::String(s) => write!(f, "{:?}", s),
+            Token::Array(tokens) | Token::FixedArray(tokens) => {
+                let tokens: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+                write!(f, "[{}]", tokens.join(", "))
+            }
+            Token::Tuple(tokens) => {
+                let tokens: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", tokens.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub kind: ParamType,
+    /// Only meaningful for event parameters.
+    pub indexed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<Param>,
+}
+
+impl Function {
+    /// Parses a human readable signature such as `transfer(address to, uint256 value)`.
+    /// Tuple parameters are not supported in this form.
+    pub fn parse(signature: &str) -> Result<Function, AbiError> {
+        let (name, inputs) = parse_signature(signature)?;
+        Ok(Function { name, inputs })
+    }
+
+    /// The canonical signature, e.g. `transfer(address,uint256)`.
+    pub fn signature(&self) -> String {
+        canonical_signature(&self.name, &self.inputs)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = keccak256(self.signature().as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    /// Decodes call data (including the selector) for this function.
+    pub fn decode_input(&self, input: &[u8]) -> Result<DecodedCall, AbiError> {
+        if input.len() < 4 || input[..4] != self.selector() {
+            return Err(AbiError::InvalidData(format!("input does not start with the selector of {}", self.signature())));
+        }
+        let kinds: Vec<ParamType> = self.inputs.iter().map(|p| p.kind.clone()).collect();
+        let tokens = decode(&kinds, &input[4..])?;
+        let args = self.inputs.iter().map(|p| p.name.clone()).zip(tokens).collect();
+        Ok(DecodedCall { name: self.name.clone(), signature: self.signature(), args })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub inputs: Vec<Param>,
+    pub anonymous: bool,
+}
+
+impl Event {
+    /// Parses a human readable signature such as `Transfer(address indexed from, address indexed to, uint256 value)`.
+    pub fn parse(signature: &str) -> Result<Event, AbiError> {
+        let (name, inputs) = parse_signature(signature)?;
+        Ok(Event { name, inputs, anonymous: false })
+    }
+
+    /// The canonical signature, e.g. `Transfer(address,address,uint256)`.
+    pub fn signature(&self) -> String {
+        canonical_signature(&self.name, &self.inputs)
+    }
+
+    /// The hash of the signature, which is the first topic of non-anonymous events.
+    pub fn topic(&self) -> [u8; 32] {
+        keccak256(self.signature().as_bytes())
+    }
+
+    /// Decodes a log emitted by this event from its raw topics and data.
+    /// Indexed parameters of dynamic types are only available as the hash stored in their topic.
+    pub fn decode_log(&self, topics: &[[u8; 32]], data: &[u8]) -> Result<DecodedLog, AbiError> {
+        let mut topics = topics.iter();
+        if !self.anonymous {
+            match topics.next() {
+                Some(topic) if *topic == self.topic() => {}
+                _ => return Err(AbiError::InvalidData(format!("log is not a {} event", self.signature()))),
+            }
+        }
+        let indexed = self.inputs.iter().filter(|p| p.indexed).count();
+        if topics.len() != indexed {
+            return Err(AbiError::InvalidData(format!("expected {} indexed topics for {}, got {}", indexed, self.signature(), topics.len())));
+        }
+        let kinds: Vec<ParamType> = self.inputs.iter().filter(|p| !p.indexed).map(|p| p.kind.clone()).collect();
+        let mut values = decode(&kinds, data)?.into_iter();
+        let mut params = Vec::with_capacity(self.inputs.len());
+        for param in &self.inputs {
+            let token = if param.indexed {
+                let topic = topics.next().expect("topic count was checked");
+                if param.kind.is_dynamic() || matches!(param.kind, ParamType::Tuple(_) | ParamType::FixedArray(_, _)) {
+                    Token::FixedBytes(topic.to_vec())
+                } else {
+                    decode_static(&param.kind, topic)?
+                }
+            } else {
+                values.next().expect("one value per non-indexed param")
+            };
+            params.push((param.name.clone(), token));
+        }
+        Ok(DecodedLog { name: self.name.clone(), signature: self.signature(), params })
+    }
+}
+
+/// An event decoded from a log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedLog {
+    pub name: String,
+    pub signature: String,
+    /// The parameters by name, in declaration order.
+    pub params: Vec<(String, Token)>,
+}
+
+impl DecodedLog {
+    pub fn param(&self, name: &str) -> Option<&Token> {
+        self.params.iter().find(|(n, _)| n == name).map(|(_, t)| t)
+    }
+}
+
+impl fmt::Display for DecodedLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params: Vec<String> = self.params.iter()
+            .map(|(name, token)| if name.is_empty() { token.to_string() } else { format!("{}: {}", name, token) })
+            .collect();
+        write!(f, "{}({})", self.name, params.join(", "))
+    }
+}
+
+/// A contract ABI as returned by the contract module or produced by solc.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Abi {
+    pub functions: Vec<Function>,
+    pub events: Vec<Event>,
+}
+
+impl Abi {
+    /// Parses the JSON representation of an ABI.
+    pub fn from_json(json: &str) -> Result<Abi, AbiError> {
+        let entries: Vec<AbiEntry> = serde_json::from_str(json).map_err(|e| AbiError::InvalidAbi(e.to_string()))?;
+        let mut abi = Abi::default();
+        for entry in entries {
+            match entry.kind.as_str() {
+                "function" => abi.functions.push(Function { name: entry.name, inputs: to_params(&entry.inputs)? }),
+                "event" => abi.events.push(Event { name: entry.name, inputs: to_params(&entry.inputs)?, anonymous: entry.anonymous }),
+                _ => {}
+            }
+        }
+        Ok(abi)
+    }
+
+    /// An ABI made up of common ERC20, ERC721, ERC1155 and WETH functions.
+    pub fn known() -> Abi {
+        let functions = KNOWN_FUNCTIONS.iter()
+            .map(|signature| Function::parse(signature).expect("known function signatures are valid"))
+            .collect();
+        Abi { functions, events: vec![] }
+    }
+
+    pub fn function_by_selector(&self, selector: &[u8]) -> Option<&Function> {
+        self.functions.iter().find(|f| f.selector() == selector)
+    }
+
+    /// Decodes a log with the first event of the ABI matching its topics.
+    pub fn decode_log(&self, topics: &[[u8; 32]], data: &[u8]) -> Result<DecodedLog, AbiError> {
+        let topic0 = topics.first().ok_or_else(|| AbiError::InvalidData("log has no topics".into()))?;
+        self.events.iter()
+            .filter(|e| !e.anonymous && e.topic() == *topic0)
+            .find_map(|e| e.decode_log(topics, data).ok())
+            .ok_or_else(|| AbiError::UnknownSelector(format!("0x{}", hex::encode(topic0))))
+    }
+
+    /// Decodes `0x` prefixed call data such as `Transaction.input`.
+    pub fn decode_input(&self, input: &str) -> Result<DecodedCall, AbiError> {
+        let input = decode_hex(input)?;
+        if input.len() < 4 {
+            return Err(AbiError::InvalidData("input is shorter than a selector".into()));
+        }
+        match self.function_by_selector(&input[..4]) {
+            Some(function) => function.decode_input(&input),
+            None => Err(AbiError::UnknownSelector(format!("0x{}", hex::encode(&input[..4])))),
+        }
+    }
+}
+
+/// The functions of an ABI by selector, hashed once for decoding many inputs.
+#[derive(Debug, Default)]
+pub(crate) struct FunctionIndex {
+    functions: HashMap<[u8; 4], Function>,
+}
+
+impl FunctionIndex {
+    pub(crate) fn new(abi: &Abi) -> FunctionIndex {
+        let mut functions = HashMap::new();
+        // the first function of a selector wins, like in Abi::function_by_selector
+        for function in &abi.functions {
+            functions.entry(function.selector()).or_insert_with(|| function.clone());
+        }
+        FunctionIndex { functions }
+    }
+
+    /// The index of `Abi::known()`, built on first use.
+    pub(crate) fn known() -> &'static FunctionIndex {
+        static KNOWN: OnceLock<FunctionIndex> = OnceLock::new();
+        KNOWN.get_or_init(|| FunctionIndex::new(&Abi::known()))
+    }
+
+    /// Decodes `0x` prefixed call data like `Abi::decode_input`.
+    pub(crate) fn decode_input(&self, input: &str) -> Result<DecodedCall, AbiError> {
+        let input = decode_hex(input)?;
+        if input.len() < 4 {
+            return Err(AbiError::InvalidData("input is shorter than a selector".into()));
+        }
+        match self.functions.get(&input[..4]) {
+            Some(function) => function.decode_input(&input),
+            None => Err(AbiError::UnknownSelector(format!("0x{}", hex::encode(&input[..4])))),
+        }
+    }
+}
+
+/// A function call decoded from transaction input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedCall {
+    pub name: String,
+    pub signature: String,
+    /// The arguments by parameter name, in declaration order.
+    pub args: Vec<(String, Token)>,
+}
+
+impl DecodedCall {
+    pub fn arg(&self, name: &str) -> Option<&Token> {
+        self.args.iter().find(|(n, _)| n == name).map(|(_, t)| t)
+    }
+}
+
+impl fmt::Display for DecodedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args: Vec<String> = self.args.iter()
+            .map(|(name, token)| if name.is_empty() { token.to_string() } else { format!("{}: {}", name, token) })
+            .collect();
+        write!(f, "{}({})", self.name, args.join(", "))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AbiEntry {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    inputs: Vec<AbiParam>,
+    #[serde(default)]
+    anonymous: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AbiParam {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    components: Vec<AbiParam>,
+    #[serde(default)]
+    indexed: bool,
+}
+
+fn to_params(params: &[AbiParam]) -> Result<Vec<Param>, AbiError> {
+    params.iter()
+        .map(|p| {
+            let components = to_params(&p.components)?;
+            Ok(Param { name: p.name.clone(), kind: ParamType::parse_with_components(&p.kind, &components)?, indexed: p.indexed })
+        })
+        .collect()
+}
+
+fn parse_signature(signature: &str) -> Result<(String, Vec<Param>), AbiError> {
+    let invalid = || AbiError::InvalidAbi(format!("invalid signature '{}'", signature));
+    let open = signature.find('(').ok_or_else(invalid)?;
+    let params = signature[open + 1..].strip_suffix(')').ok_or_else(invalid)?;
+    let mut inputs = vec![];
+    for param in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let mut parts = param.split_whitespace();
+        let kind = ParamType::parse(parts.next().ok_or_else(invalid)?)?;
+        let mut indexed = false;
+        let mut name = String::new();
+        for part in parts {
+            match part {
+                "indexed" => indexed = true,
+                _ => name = part.into(),
+            }
+        }
+        inputs.push(Param { name, kind, indexed });
+    }
+    Ok((signature[..open].trim().into(), inputs))
+}
+
+fn canonical_signature(name: &str, inputs: &[Param]) -> String {
+    let kinds: Vec<String> = inputs.iter().map(|p| p.kind.to_string()).collect();
+    format!("{}({})", name, kinds.join(","))
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(data);
+    hasher.finalize(&mut output);
+    output
+}
+
+pub(crate) fn decode_hex(s: &str) -> Result<Vec<u8>, AbiError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    hex::decode(s).map_err(|e| AbiError::InvalidData(e.to_string()))
+}
+
+pub(crate) fn decode_topic(s: &str) -> Result<[u8; 32], AbiError> {
+    let bytes = decode_hex(s)?;
+    if bytes.len() != 32 {
+        return Err(AbiError::InvalidData(format!("topic {} is not 32 bytes long", s)));
+    }
+    let mut topic = [0u8; 32];
+    topic.copy_from_slice(&bytes);
+    Ok(topic)
+}
+
+/// Decodes ABI encoded data made up of the given types.
+pub fn decode(kinds: &[ParamType], data: &[u8]) -> Result<Vec<Token>, AbiError> {
+    let mut tokens = Vec::with_capacity(kinds.len());
+    let mut head = 0;
+    for kind in kinds {
+        if kind.is_dynamic() {
+            let offset = read_usize(data, head)?;
+            let tail = data.get(offset..).ok_or_else(|| out_of_bounds(offset))?;
+            tokens.push(decode_dynamic(kind, tail)?);
+        } else {
+            let inline = data.get(head..).ok_or_else(|| out_of_bounds(head))?;
+            tokens.push(decode_static(kind, inline)?);
+        }
+        head += kind.head_size();
+    }
+    Ok(tokens)
+}
+
+fn decode_static(kind: &ParamType, data: &[u8]) -> Result<Token, AbiError> {
+    match kind {
+        ParamType::Address => Ok(Token::Address(format!("0x{}", hex::encode(&read_word(data, 0)?[12..])))),
+        ParamType::Uint(_) => Ok(Token::Uint(U256::from_big_endian(read_word(data, 0)?))),
+        ParamType::Int(_) => Ok(Token::Int(U256::from_big_endian(read_word(data, 0)?))),
+        ParamType::Bool => Ok(Token::Bool(read_word(data, 0)?[31] != 0)),
+        ParamType::FixedBytes(size) => Ok(Token::FixedBytes(read_word(data, 0)?[..*size.min(&32)].to_vec())),
+        ParamType::FixedArray(element, size) => Ok(Token::FixedArray(decode(&vec![(**element).clone(); *size], data)?)),
+        ParamType::Tuple(elements) => Ok(Token::Tuple(decode(elements, data)?)),
+        _ => decode_dynamic(kind, data),
+    }
+}
+
+fn decode_dynamic(kind: &ParamType, data: &[u8]) -> Result<Token, AbiError> {
+    match kind {
+        ParamType::Bytes => Ok(Token::Bytes(read_bytes(data)?.to_vec())),
+        ParamType::String => Ok(Token::String(String::from_utf8_lossy(read_bytes(data)?).into_owned())),
+        ParamType::Array(element) => {
+            let len = read_usize(data, 0)?;
+            let elements = data.get(32..).ok_or_else(|| out_of_bounds(32))?;
+            // every element takes at least one word, which guards against absurd lengths
+            if len > elements.len() / 32 + 1 {
+                return Err(AbiError::InvalidData(format!("array length {} exceeds data", len)));
+            }
+            Ok(Token::Array(decode(&vec![(**element).clone(); len], elements)?))
+        }
+        ParamType::FixedArray(element, size) => Ok(Token::FixedArray(decode(&vec![(**element).clone(); *size], data)?)),
+        ParamType::Tuple(elements) => Ok(Token::Tuple(decode(elements, data)?)),
+        _ => decode_static(kind, data),
+    }
+}
+
+fn read_word(data: &[u8], offset: usize) -> Result<&[u8], AbiError> {
+    data.get(offset..offset + 32).ok_or_else(|| out_of_bounds(offset))
+}
+
+fn read_usize(data: &[u8], offset: usize) -> Result<usize, AbiError> {
+    let value = U256::from_big_endian(read_word(data, offset)?);
+    if value > U256::from(u32::MAX) {
+        return Err(AbiError::InvalidData(format!("offset or length {} is too large", value)));
+    }
+    Ok(value.as_usize())
+}
+
+fn read_bytes(data: &[u8]) -> Result<&[u8], AbiError> {
+    let len = read_usize(data, 0)?;
+    data.get(32..32 + len).ok_or_else(|| out_of_bounds(32 + len))
+}
+
+fn out_of_bounds(offset: usize) -> AbiError {
+    AbiError::InvalidData(format!("read at offset {} is out of bounds", offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors() {
+        assert_eq!(hex::encode(Function::parse("transfer(address to, uint256 value)").unwrap().selector()), "a9059cbb");
+        assert_eq!(hex::encode(Function::parse("approve(address,uint256)").unwrap().selector()), "095ea7b3");
+        assert_eq!(hex::encode(Event::parse("Transfer(address indexed from, address indexed to, uint256 value)").unwrap().topic()),
+                   "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+    }
+
+    #[test]
+    fn decode_known_transfer() {
+        let input = "0xa9059cbb000000000000000000000000a0b86991c6218b36c1d19d4a2e9eb0ce3606eb480000000000000000000000000000000000000000000000000de0b6b3a7640000";
+        let call = Abi::known().decode_input(input).unwrap();
+        assert_eq!(call.name, "transfer");
+        assert_eq!(call.arg("to").and_then(Token::as_address), Some("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"));
+        assert_eq!(call.arg("value").and_then(Token::as_uint), Some(U256::from(1_000_000_000_000_000_000u64)));
+        assert_eq!(call.to_string(), "transfer(to: 0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48, value: 1000000000000000000)");
+    }
+
+    #[test]
+    fn decode_dynamic_types_from_json_abi() {
+        let abi = Abi::from_json(r#"[{"type":"function","name":"f","inputs":[{"name":"a","type":"uint256[]"},{"name":"s","type":"string"},{"name":"t","type":"tuple","components":[{"name":"x","type":"int8"},{"name":"y","type":"bool"}]}]}]"#).unwrap();
+        let function = &abi.functions[0];
+        assert_eq!(function.signature(), "f(uint256[],string,(int8,bool))");
+
+        let mut input = function.selector().to_vec();
+        let words = [
+            "0000000000000000000000000000000000000000000000000000000000000080", // offset of a
+            "00000000000000000000000000000000000000000000000000000000000000e0", // offset of s
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff", // t.x = -1
+            "0000000000000000000000000000000000000000000000000000000000000001", // t.y = true
+            "0000000000000000000000000000000000000000000000000000000000000002", // len(a)
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000002", // len(s)
+            "6869000000000000000000000000000000000000000000000000000000000000",
+        ];
+        for word in words.iter() {
+            input.extend(hex::decode(word).unwrap());
+        }
+        let call = abi.decode_input(&format!("0x{}", hex::encode(input))).unwrap();
+        assert_eq!(call.to_string(), "f(a: [1, 2], s: \"hi\", t: (-1, true))");
+    }
+
+    #[test]
+    fn reject_truncated_input() {
+        assert!(Abi::known().decode_input("0xa9059cbb0000").is_err());
+        assert_eq!(Abi::known().decode_input("0xdeadbeef"), Err(AbiError::UnknownSelector("0xdeadbeef".into())));
+    }
+}
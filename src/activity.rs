@@ -0,0 +1,98 @@
+//! An account's history as one timeline, joining normal transactions with the internal transactions
+//! and token transfers they caused.
+
+use std::collections::HashMap;
+
+use super::models::{ERC20TokenTransferEvent, ERC721TokenTransferEvent, InternalTransaction, Transaction};
+
+/// Everything a transaction did to an account.
+#[derive(Debug)]
+pub struct ActivityEntry {
+    hash: String,
+    block_number: u64,
+    timestamp: u64,
+    transaction_index: Option<u64>,
+    transaction: Option<Transaction>,
+    internal_txs: Vec<InternalTransaction>,
+    erc20_transfers: Vec<ERC20TokenTransferEvent>,
+    erc721_transfers: Vec<ERC721TokenTransferEvent>,
+}
+
+impl ActivityEntry {
+    fn new(hash: &str, block_number: u64, timestamp: u64) -> ActivityEntry {
+        ActivityEntry {
+            hash: hash.into(),
+            block_number,
+            timestamp,
+            transaction_index: None,
+            transaction: None,
+            internal_txs: vec![],
+            erc20_transfers: vec![],
+            erc721_transfers: vec![],
+        }
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// The transaction if the account sent or received it, `None` if the account was only touched
+    /// by internal transactions or token transfers of someone else's transaction.
+    pub fn transaction(&self) -> Option<&Transaction> {
+        self.transaction.as_ref()
+    }
+
+    pub fn internal_txs(&self) -> &[InternalTransaction] {
+        &self.internal_txs
+    }
+
+    pub fn erc20_transfers(&self) -> &[ERC20TokenTransferEvent] {
+        &self.erc20_transfers
+    }
+
+    pub fn erc721_transfers(&self) -> &[ERC721TokenTransferEvent] {
+        &self.erc721_transfers
+    }
+}
+
+/// Joins the lists by tx hash, ordered by block and position within the block.
+pub(crate) fn timeline(txs: Vec<Transaction>, internal_txs: Vec<InternalTransaction>,
+                       erc20_transfers: Vec<ERC20TokenTransferEvent>, erc721_transfers: Vec<ERC721TokenTransferEvent>) -> Vec<ActivityEntry> {
+    let mut entries: HashMap<String, ActivityEntry> = HashMap::new();
+    for tx in txs {
+        let e = entry(&mut entries, tx.hash(), tx.block_number(), tx.timestamp());
+        e.transaction_index = Some(tx.transaction_index());
+        e.transaction = Some(tx);
+    }
+    for transfer in erc20_transfers {
+        let e = entry(&mut entries, transfer.hash(), transfer.block_number(), transfer.timestamp());
+        e.transaction_index = Some(transfer.transaction_index());
+        e.erc20_transfers.push(transfer);
+    }
+    for transfer in erc721_transfers {
+        let e = entry(&mut entries, transfer.hash(), transfer.block_number(), transfer.timestamp());
+        e.transaction_index = Some(transfer.transaction_index());
+        e.erc721_transfers.push(transfer);
+    }
+    for internal_tx in internal_txs {
+        entry(&mut entries, internal_tx.hash(), internal_tx.block_number(), internal_tx.timestamp()).internal_txs.push(internal_tx);
+    }
+    let mut entries: Vec<ActivityEntry> = entries.into_values().collect();
+    // internal transactions don't reveal their position, those entries go last within their block
+    entries.sort_by(|a, b| (a.block_number, a.transaction_index.unwrap_or(u64::MAX), &a.hash)
+        .cmp(&(b.block_number, b.transaction_index.unwrap_or(u64::MAX), &b.hash)));
+    entries
+}
+
+fn entry<'a>(entries: &'a mut HashMap<String, ActivityEntry>, hash: &str, block_number: u64, timestamp: u64) -> &'a mut ActivityEntry {
+    // Etherscan's hashes are lower case, but be safe when joining
+    entries.entry(hash.to_lowercase()).or_insert_with(|| ActivityEntry::new(hash, block_number, timestamp))
+}
@@ -0,0 +1,391 @@
+//! Command line client for ad-hoc queries, e.g.
+//!
+//! ```text
+//! ETHERSCANIO_API_TOKEN=... etherscan --output csv txs 0xddbd2b932c763ba5b1b7ae3b362eac3e8d40121a
+//! ```
+
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use etherscanio::{LogFilter, API};
+use etherscanio::models::{Chain, ClientType, SyncMode};
+use etherscanio::units::{format_ether, format_gwei};
+
+type AsyncError = Box<dyn std::error::Error + Send + Sync>;
+
+// the end block Etherscan reads as the latest one
+const LATEST_BLOCK: u64 = 99_999_999;
+
+#[derive(Clone, Copy)]
+enum Output {
+    Table,
+    Json,
+    Csv,
+}
+
+#[tokio::main]
+async fn main() {
+    let matches = app().get_matches();
+    let output = match matches.value_of("output").unwrap_or("table") {
+        "json" => Output::Json,
+        "csv" => Output::Csv,
+        _ => Output::Table,
+    };
+    let api = match API::new_from_env() {
+        Ok(api) => api,
+        Err(e) => {
+            eprintln!("error: couldn't read ETHERSCANIO_API_TOKEN: {}", e);
+            std::process::exit(1);
+        }
+    };
+    match run(&matches, api).await {
+        Ok(value) => print(&value, output),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    let address = || Arg::with_name("address").required(true).help("the account address");
+    let contract = || Arg::with_name("contract").required(true).help("the contract address");
+    let tx_hash = || Arg::with_name("txhash").required(true).help("the transaction hash");
+    let from_block = || Arg::with_name("from-block").long("from-block").takes_value(true).help("the first block to include");
+    let to_block = || Arg::with_name("to-block").long("to-block").takes_value(true).help("the last block to include");
+    let token = || Arg::with_name("token").long("token").takes_value(true).help("only include transfers of this token contract")
+        .conflicts_with_all(&["from-block", "to-block"]);
+    let start = || Arg::with_name("start").required(true).help("the first day, as YYYY-MM-DD");
+    let end = || Arg::with_name("end").required(true).help("the last day, as YYYY-MM-DD");
+
+    App::new("etherscan")
+        .about("Queries the etherscan.io API, reading the API key from ETHERSCANIO_API_TOKEN")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(Arg::with_name("chain").long("chain").global(true).takes_value(true)
+            .possible_values(&["mainnet", "goerli", "sepolia", "holesky"]).help("the network to query [default: mainnet]"))
+        .arg(Arg::with_name("output").long("output").short("o").global(true).takes_value(true)
+            .possible_values(&["table", "json", "csv"]).help("the output format [default: table]"))
+        .subcommand(SubCommand::with_name("balance").about("ether balance of an account").arg(address()))
+        .subcommand(SubCommand::with_name("token-balance").about("ERC20 token balance of an account").arg(address()).arg(contract()))
+        .subcommand(SubCommand::with_name("token-supply").about("total supply of an ERC20 token").arg(contract()))
+        .subcommand(SubCommand::with_name("txs").about("normal transactions of an account").arg(address()).arg(from_block()).arg(to_block()))
+        .subcommand(SubCommand::with_name("internal-txs").about("internal transactions of an account or a transaction")
+            .arg(Arg::with_name("address").required_unless("txhash").help("the account address"))
+            .arg(Arg::with_name("txhash").long("txhash").takes_value(true).help("list the internal transactions of this transaction instead"))
+            .arg(from_block()).arg(to_block()))
+        .subcommand(SubCommand::with_name("erc20-transfers").about("ERC20 transfers of an account").arg(address()).arg(token()).arg(from_block()).arg(to_block()))
+        .subcommand(SubCommand::with_name("erc721-transfers").about("ERC721 transfers of an account").arg(address()).arg(token()).arg(from_block()).arg(to_block()))
+        .subcommand(SubCommand::with_name("mined-blocks").about("blocks mined by an account").arg(address()))
+        .subcommand(SubCommand::with_name("logs").about("event logs of a contract").arg(contract()).arg(from_block()).arg(to_block())
+            .arg(Arg::with_name("topic0").long("topic0").takes_value(true).help("only include logs with this first topic")))
+        .subcommand(SubCommand::with_name("gas-oracle").about("current safe, proposed and fast gas prices"))
+        .subcommand(SubCommand::with_name("gas-estimate").about("estimated confirmation time in seconds for a gas price")
+            .arg(Arg::with_name("gasprice").required(true).help("the gas price in wei")))
+        .subcommand(SubCommand::with_name("eth-price").about("current ETH price in BTC and USD"))
+        .subcommand(SubCommand::with_name("eth-supply").about("ETH supply including staking rewards, burnt fees and withdrawals"))
+        .subcommand(SubCommand::with_name("node-count").about("number of discoverable nodes"))
+        .subcommand(SubCommand::with_name("chain-size").about("size of the chain data per day").arg(start()).arg(end())
+            .arg(Arg::with_name("client").long("client").takes_value(true).possible_values(&["geth", "parity"]))
+            .arg(Arg::with_name("archive").long("archive").help("report the size of an archive node")))
+        .subcommand(SubCommand::with_name("daily").about("daily network statistics")
+            .arg(Arg::with_name("stat").required(true).possible_values(&[
+                "avg-gas-limit", "gas-used", "avg-gas-price", "tx-count", "new-addresses", "network-utilization", "tx-fee",
+                "hash-rate", "difficulty", "block-size", "block-count", "block-time", "uncles", "market-cap", "price",
+            ]))
+            .arg(start()).arg(end()))
+        .subcommand(SubCommand::with_name("tx-status").about("execution and receipt status of a transaction").arg(tx_hash()))
+        .subcommand(SubCommand::with_name("abi").about("functions and events of a verified contract").arg(contract()))
+        .subcommand(SubCommand::with_name("decode-input").about("decode the input of a call to a contract").arg(contract())
+            .arg(Arg::with_name("input").required(true).help("the 0x prefixed call data")))
+}
+
+async fn run(matches: &ArgMatches<'_>, mut api: API) -> Result<Value, AsyncError> {
+    let (command, args) = matches.subcommand();
+    let args = args.expect("a subcommand is required");
+    if let Some(chain) = args.value_of("chain").or_else(|| matches.value_of("chain")) {
+        api = api.with_chain(Chain::from_str(chain)?);
+    }
+    let address = || args.value_of("address").unwrap_or_default();
+    let contract = || args.value_of("contract").unwrap_or_default();
+    // `(0, 0)` asks for the whole list, `None` for an empty range. A missing --from-block starts at
+    // genesis, a missing --to-block runs to the latest block. Genesis holds no transactions, so a
+    // range ending there is empty.
+    let blocks = || -> Result<Option<(u64, u64)>, AsyncError> {
+        let from_block: Option<u64> = args.value_of("from-block").map(str::parse).transpose()?;
+        let to_block: Option<u64> = args.value_of("to-block").map(str::parse).transpose()?;
+        if from_block.is_none() && to_block.is_none() {
+            return Ok(Some((0, 0)));
+        }
+        let (from_block, to_block) = (from_block.unwrap_or(0), to_block.unwrap_or(LATEST_BLOCK));
+        Ok(if to_block == 0 || from_block > to_block { None } else { Some((from_block, to_block)) })
+    };
+    let date = |name: &str| -> Result<NaiveDate, AsyncError> {
+        Ok(NaiveDate::parse_from_str(args.value_of(name).unwrap_or_default(), "%Y-%m-%d")?)
+    };
+
+    let value = match command {
+        "balance" => {
+            let balance = api.acc_balance(address()).await?;
+            json!({ "address": address(), "wei": balance.to_string(), "ether": format_ether(balance) })
+        }
+        "token-balance" => {
+            let balance = api.erc20_token_balance_on_account(address(), contract()).await?;
+            json!({ "address": address(), "contract": contract(), "balance": balance.to_string() })
+        }
+        "token-supply" => {
+            let supply = api.erc20_token_total_supply(contract()).await?;
+            json!({ "contract": contract(), "total_supply": supply.to_string() })
+        }
+        "txs" => match blocks()? {
+            Some((from_block, to_block)) => to_value(api.txs_on_account_from_to(address(), from_block, to_block).await?)?,
+            None => json!([]),
+        },
+        "internal-txs" => match (args.value_of("txhash"), blocks()?) {
+            (Some(tx_hash), _) => to_value(api.internal_txs_by_tx_hash(tx_hash).await?)?,
+            (None, Some((from_block, to_block))) => to_value(api.internal_txs_on_account_from_to(address(), from_block, to_block).await?)?,
+            (None, None) => json!([]),
+        },
+        "erc20-transfers" => match (args.value_of("token"), blocks()?) {
+            (Some(token), _) => to_value(api.erc20_transfers_on_account_by_contract(address(), token).await?)?,
+            (None, Some((from_block, to_block))) => to_value(api.erc20_transfers_on_account_from_to(address(), from_block, to_block).await?)?,
+            (None, None) => json!([]),
+        },
+        "erc721-transfers" => match (args.value_of("token"), blocks()?) {
+            (Some(token), _) => to_value(api.erc271_transfers_on_account_by_contract(address(), token).await?)?,
+            (None, Some((from_block, to_block))) => to_value(api.erc271_transfers_on_account_from_to(address(), from_block, to_block).await?)?,
+            (None, None) => json!([]),
+        },
+        "mined-blocks" => to_value(api.mined_blocks_by_account(address()).await?)?,
+        "logs" => {
+            let mut filter = LogFilter { address: Some(contract().into()), ..LogFilter::default() };
+            filter.from_block = args.value_of("from-block").map(str::parse).transpose()?;
+            filter.to_block = args.value_of("to-block").map(str::parse).transpose()?;
+            filter.topics[0] = args.value_of("topic0").map(String::from);
+            to_value(api.logs(&filter).await?)?
+        }
+        "gas-oracle" => {
+            let oracle = api.gas_oracle().await?;
+            json!({
+                "last_block": oracle.last_block(),
+                "safe_gwei": format_gwei(oracle.safe_gas_price()),
+                "propose_gwei": format_gwei(oracle.propose_gas_price()),
+                "fast_gwei": format_gwei(oracle.fast_gas_price()),
+                "base_fee_gwei": format_gwei(oracle.suggest_base_fee()),
+            })
+        }
+        "gas-estimate" => {
+            let gas_price: u128 = args.value_of("gasprice").unwrap_or_default().parse()?;
+            let seconds = api.estimate_conf_time_for_gas(gas_price).await?;
+            json!({ "gas_price": gas_price.to_string(), "seconds": seconds.to_string() })
+        }
+        "eth-price" => to_value(api.eth_price().await?)?,
+        "eth-supply" => to_value(api.eth_supply_breakdown().await?)?,
+        "node-count" => to_value(api.node_count().await?)?,
+        "chain-size" => {
+            let client_type = match args.value_of("client") {
+                Some("parity") => ClientType::Parity,
+                _ => ClientType::Geth,
+            };
+            let sync_mode = if args.is_present("archive") { SyncMode::Archive } else { SyncMode::Default };
+            to_value(api.chain_size(date("start")?, date("end")?, client_type, sync_mode).await?)?
+        }
+        "daily" => daily(&api, args.value_of("stat").unwrap_or_default(), date("start")?, date("end")?).await?,
+        "tx-status" => {
+            let tx_hash = args.value_of("txhash").unwrap_or_default();
+            let execution = api.contract_execution_status(tx_hash).await?.status();
+            let receipt = api.tx_receipt_status(tx_hash).await?.status();
+            json!({ "txhash": tx_hash, "execution": to_value(execution)?, "receipt": to_value(receipt)? })
+        }
+        "abi" => {
+            let abi = api.contract_abi(contract()).await?;
+            let functions = abi.functions.iter()
+                .map(|f| json!({ "type": "function", "signature": f.signature(), "selector": format!("0x{}", hex::encode(f.selector())) }));
+            let events = abi.events.iter()
+                .map(|e| json!({ "type": "event", "signature": e.signature(), "selector": format!("0x{}", hex::encode(e.topic())) }));
+            Value::Array(functions.chain(events).collect())
+        }
+        "decode-input" => {
+            let call = api.decode_input(contract(), args.value_of("input").unwrap_or_default()).await?;
+            let args: Vec<Value> = call.args.iter()
+                .map(|(name, token)| json!({ "function": call.signature, "name": name, "value": token.to_string() }))
+                .collect();
+            Value::Array(args)
+        }
+        _ => unreachable!("clap only accepts known subcommands"),
+    };
+    Ok(value)
+}
+
+async fn daily(api: &API, stat: &str, start: NaiveDate, end: NaiveDate) -> Result<Value, AsyncError> {
+    let value = match stat {
+        "avg-gas-limit" => to_value(api.daily_avg_gas_limit(start, end).await?)?,
+        "gas-used" => to_value(api.daily_gas_used(start, end).await?)?,
+        "avg-gas-price" => to_value(api.daily_avg_gas_price(start, end).await?)?,
+        "tx-count" => to_value(api.daily_tx_count(start, end).await?)?,
+        "new-addresses" => to_value(api.daily_new_address_count(start, end).await?)?,
+        "network-utilization" => to_value(api.daily_network_utilization(start, end).await?)?,
+        "tx-fee" => to_value(api.daily_tx_fee(start, end).await?)?,
+        "hash-rate" => to_value(api.daily_avg_hash_rate(start, end).await?)?,
+        "difficulty" => to_value(api.daily_avg_difficulty(start, end).await?)?,
+        "block-size" => to_value(api.daily_avg_block_size(start, end).await?)?,
+        "block-count" => to_value(api.daily_block_count(start, end).await?)?,
+        "block-time" => to_value(api.daily_avg_block_time(start, end).await?)?,
+        "uncles" => to_value(api.daily_uncle_block_count(start, end).await?)?,
+        "market-cap" => to_value(api.daily_market_cap(start, end).await?)?,
+        "price" => to_value(api.eth_daily_prices(start, end).await?)?,
+        _ => unreachable!("clap only accepts known stats"),
+    };
+    Ok(value)
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, AsyncError> {
+    Ok(serde_json::to_value(value)?)
+}
+
+fn print(value: &Value, output: Output) {
+    if let Output::Json = output {
+        println!("{}", serde_json::to_string_pretty(value).expect("values serialize"));
+        return;
+    }
+    let rows: Vec<&Value> = match value {
+        Value::Array(rows) => rows.iter().collect(),
+        row => vec![row],
+    };
+    let mut columns: Vec<String> = vec![];
+    for row in &rows {
+        match row {
+            Value::Object(fields) => {
+                for key in fields.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            _ if columns.is_empty() => columns.push("value".into()),
+            _ => {}
+        }
+    }
+    let cells: Vec<Vec<String>> = rows.iter()
+        .map(|row| match row {
+            Value::Object(fields) => columns.iter().map(|c| cell(fields.get(c).unwrap_or(&Value::Null))).collect(),
+            other => vec![cell(other)],
+        })
+        .collect();
+
+    match output {
+        Output::Csv => {
+            println!("{}", columns.iter().map(|c| csv_field(c)).collect::<Vec<_>>().join(","));
+            for row in &cells {
+                println!("{}", row.iter().map(|c| csv_field(c)).collect::<Vec<_>>().join(","));
+            }
+        }
+        _ => {
+            let widths: Vec<usize> = columns.iter().enumerate()
+                .map(|(i, c)| cells.iter().map(|row| row[i].len()).chain(std::iter::once(c.len())).max().unwrap_or(0))
+                .collect();
+            let line = |row: &[String]| -> String {
+                row.iter().zip(&widths).map(|(c, w)| format!("{:<width$}", c, width = w)).collect::<Vec<_>>().join("  ").trim_end().to_string()
+            };
+            println!("{}", line(&columns));
+            for row in &cells {
+                println!("{}", line(row));
+            }
+        }
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use etherscanio::testing::FixtureServer;
+
+    use super::*;
+
+    const ADDR: &str = "0xddbd2b932c763ba5b1b7ae3b362eac3e8d40121a";
+    const CHAIN_LINK: &str = "0x514910771af9ca656af840dff83e8264ecf986ca";
+    const FAILED_TX: &str = "0x15f8e5ea1079d9a0bb04a4c58ae5fe7654b5b2b4463375ff7ffb490aa0032f3a";
+    const TRANSFER_INPUT: &str = "0xa9059cbb0000000000000000000000004e83362442b8d1bec281594cea3050c8eb01311c0000000000000000000000000000000000000000000000000de0b6b3a7640000";
+
+    async fn run_args(args: &[&str]) -> Result<Value, AsyncError> {
+        let server = FixtureServer::start(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), Chain::Mainnet).unwrap();
+        let api = API::new("test").with_base_url(&server.base_url());
+        let matches = app().get_matches_from_safe(std::iter::once("etherscan").chain(args.iter().cloned()))?;
+        run(&matches, api).await
+    }
+
+    #[tokio::test]
+    async fn runs_every_subcommand() {
+        let commands: &[&[&str]] = &[
+            &["balance", ADDR],
+            &["token-balance", ADDR, CHAIN_LINK],
+            &["token-supply", CHAIN_LINK],
+            &["txs", ADDR],
+            &["txs", ADDR, "--from-block", "14923678", "--to-block", "99999999"],
+            &["internal-txs", ADDR],
+            &["erc20-transfers", ADDR],
+            &["erc20-transfers", ADDR, "--token", CHAIN_LINK],
+            &["erc721-transfers", ADDR],
+            &["mined-blocks", ADDR],
+            &["logs", CHAIN_LINK, "--from-block", "14923680", "--to-block", "14923700",
+              "--topic0", "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"],
+            &["gas-oracle"],
+            &["gas-estimate", "2000000000"],
+            &["eth-price"],
+            &["eth-supply"],
+            &["node-count"],
+            &["chain-size", "2019-02-01", "2019-02-28"],
+            &["daily", "gas-used", "2019-02-01", "2019-02-28"],
+            &["daily", "avg-gas-price", "2019-02-01", "2019-02-28"],
+            &["daily", "price", "2019-01-01", "2019-12-31"],
+            &["tx-status", FAILED_TX],
+            &["abi", CHAIN_LINK],
+            &["decode-input", CHAIN_LINK, TRANSFER_INPUT],
+        ];
+        for args in commands {
+            let value = run_args(args).await.unwrap_or_else(|e| panic!("{:?} failed: {}", args, e));
+            assert!(!value.is_null(), "{:?} returned nothing", args);
+        }
+    }
+
+    #[tokio::test]
+    async fn serializes_wei_amounts_as_strings() {
+        let supply = run_args(&["eth-supply"]).await.unwrap();
+        assert!(supply["eth_supply"].is_string());
+        let txs = run_args(&["txs", ADDR]).await.unwrap();
+        assert!(txs[0]["value"].is_string());
+    }
+
+    #[tokio::test]
+    async fn defaults_missing_block_bounds() {
+        // the account's first tx is in block 14923678
+        let from_only = run_args(&["txs", ADDR, "--from-block", "14923680"]).await.unwrap();
+        assert_eq!(from_only.as_array().unwrap().len(), 1);
+        assert_eq!(from_only[0]["block_number"], 14_923_692);
+        assert_eq!(run_args(&["txs", ADDR, "--to-block", "0"]).await.unwrap(), json!([]));
+        assert_eq!(run_args(&["txs", ADDR, "--from-block", "5", "--to-block", "4"]).await.unwrap(), json!([]));
+    }
+
+    #[tokio::test]
+    async fn rejects_token_with_block_range() {
+        assert!(run_args(&["erc20-transfers", ADDR, "--token", CHAIN_LINK, "--from-block", "1"]).await.is_err());
+    }
+}
@@ -0,0 +1,316 @@
+//! A synchronous client, behind the `blocking` feature.
+//!
+//! `blocking::API` offers the methods of the async `API`, which documents them. It runs the
+//! requests on a runtime of its own, so callers need none; it must not be used from within
+//! async code, which should use the async `API` instead.
+
+use std::env::VarError;
+use std::future::Future;
+use std::ops::RangeBounds;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use tokio::runtime::{Builder, Runtime};
+
+use super::abi::{Abi, DecodedCall};
+use super::activity::ActivityEntry;
+use super::cache::{Cache, CachePolicy};
+use super::keys::KeyPool;
+use super::metrics::Metrics;
+use super::models::*;
+use super::{AsyncError, Endpoint, LogFilter};
+
+#[derive(Debug)]
+pub struct API {
+    inner: super::API,
+    runtime: Runtime,
+}
+
+impl API {
+    /// Panics if the runtime can't be started, like `new` of the async `API` when its HTTP client can't.
+    pub fn new(api_token: &str) -> API {
+        API::from_async(super::API::new(api_token))
+    }
+
+    pub fn new_from_env() -> Result<API, VarError> {
+        Ok(API::from_async(super::API::new_from_env()?))
+    }
+
+    fn from_async(inner: super::API) -> API {
+        let runtime = Builder::new()
+            .threaded_scheduler()
+            .core_threads(1)
+            .enable_all()
+            .build()
+            .expect("starting the blocking client's runtime failed");
+        API { inner, runtime }
+    }
+
+    fn map(self, f: impl FnOnce(super::API) -> super::API) -> API {
+        API { inner: f(self.inner), runtime: self.runtime }
+    }
+
+    pub fn with_base_url(self, base_url: &str) -> API {
+        self.map(|api| api.with_base_url(base_url))
+    }
+
+    pub fn with_chain(self, chain: Chain) -> API {
+        self.map(|api| api.with_chain(chain))
+    }
+
+    pub fn with_cache(self, cache: Arc<dyn Cache>) -> API {
+        self.map(|api| api.with_cache(cache))
+    }
+
+    pub fn with_cache_policy(self, cache_policy: CachePolicy) -> API {
+        self.map(|api| api.with_cache_policy(cache_policy))
+    }
+
+    pub fn with_rate_limit(self, requests_per_second: u32) -> API {
+        self.map(|api| api.with_rate_limit(requests_per_second))
+    }
+
+    pub fn with_key_pool(self, key_pool: KeyPool) -> API {
+        self.map(|api| api.with_key_pool(key_pool))
+    }
+
+    pub fn with_metrics(self, metrics: Arc<dyn Metrics>) -> API {
+        self.map(|api| api.with_metrics(metrics))
+    }
+
+    pub fn request_interval(&self) -> Duration {
+        self.inner.request_interval()
+    }
+
+    /// Polls the future on the calling thread while the runtime drives its I/O and timers.
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.handle().enter(|| futures::executor::block_on(future))
+    }
+
+    pub fn call<E: Endpoint>(&self, endpoint: E) -> Result<E::Response, AsyncError> {
+        self.block_on(self.inner.call(endpoint))
+    }
+
+    pub fn raw(&self, module: &str, action: &str, params: &[(&str, &str)]) -> Result<serde_json::Value, AsyncError> {
+        self.block_on(self.inner.raw(module, action, params))
+    }
+
+    pub fn acc_balance(&self, account_addr: &str) -> Result<u128, AsyncError> {
+        self.block_on(self.inner.acc_balance(account_addr))
+    }
+
+    pub fn estimate_conf_time_for_gas(&self, gas: u128) -> Result<u128, AsyncError> {
+        self.block_on(self.inner.estimate_conf_time_for_gas(gas))
+    }
+
+    pub fn gas_oracle(&self) -> Result<GasOracle, AsyncError> {
+        self.block_on(self.inner.gas_oracle())
+    }
+
+    pub fn daily_avg_gas_limit(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyGasLimit>>, AsyncError> {
+        self.block_on(self.inner.daily_avg_gas_limit(start_date, end_date))
+    }
+
+    pub fn daily_gas_used(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyGasUsed>>, AsyncError> {
+        self.block_on(self.inner.daily_gas_used(start_date, end_date))
+    }
+
+    pub fn daily_avg_gas_price(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyGasPrice>>, AsyncError> {
+        self.block_on(self.inner.daily_avg_gas_price(start_date, end_date))
+    }
+
+    pub fn eth_price(&self) -> Result<ETHPrice, AsyncError> {
+        self.block_on(self.inner.eth_price())
+    }
+
+    pub fn eth_daily_prices(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyPrice>>, AsyncError> {
+        self.block_on(self.inner.eth_daily_prices(start_date, end_date))
+    }
+
+    pub fn price_at(&self, timestamp: DateTime<Utc>) -> Result<f64, AsyncError> {
+        self.block_on(self.inner.price_at(timestamp))
+    }
+
+    pub fn eth_supply(&self) -> Result<u128, AsyncError> {
+        self.block_on(self.inner.eth_supply())
+    }
+
+    pub fn eth_supply_breakdown(&self) -> Result<ETHSupply, AsyncError> {
+        self.block_on(self.inner.eth_supply_breakdown())
+    }
+
+    pub fn chain_size(&self, start_date: NaiveDate, end_date: NaiveDate, client_type: ClientType, sync_mode: SyncMode) -> Result<Vec<ChainSize>, AsyncError> {
+        self.block_on(self.inner.chain_size(start_date, end_date, client_type, sync_mode))
+    }
+
+    pub fn node_count(&self) -> Result<NodeCount, AsyncError> {
+        self.block_on(self.inner.node_count())
+    }
+
+    pub fn daily_tx_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyTxCount>>, AsyncError> {
+        self.block_on(self.inner.daily_tx_count(start_date, end_date))
+    }
+
+    pub fn daily_new_address_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyNewAddressCount>>, AsyncError> {
+        self.block_on(self.inner.daily_new_address_count(start_date, end_date))
+    }
+
+    pub fn daily_network_utilization(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyNetworkUtilization>>, AsyncError> {
+        self.block_on(self.inner.daily_network_utilization(start_date, end_date))
+    }
+
+    pub fn daily_tx_fee(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyTxFee>>, AsyncError> {
+        self.block_on(self.inner.daily_tx_fee(start_date, end_date))
+    }
+
+    pub fn daily_avg_hash_rate(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyHashRate>>, AsyncError> {
+        self.block_on(self.inner.daily_avg_hash_rate(start_date, end_date))
+    }
+
+    pub fn daily_avg_difficulty(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyDifficulty>>, AsyncError> {
+        self.block_on(self.inner.daily_avg_difficulty(start_date, end_date))
+    }
+
+    pub fn daily_avg_block_size(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyBlockSize>>, AsyncError> {
+        self.block_on(self.inner.daily_avg_block_size(start_date, end_date))
+    }
+
+    pub fn daily_block_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyBlockCount>>, AsyncError> {
+        self.block_on(self.inner.daily_block_count(start_date, end_date))
+    }
+
+    pub fn daily_avg_block_time(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyBlockTime>>, AsyncError> {
+        self.block_on(self.inner.daily_avg_block_time(start_date, end_date))
+    }
+
+    pub fn daily_uncle_block_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyUncleBlockCount>>, AsyncError> {
+        self.block_on(self.inner.daily_uncle_block_count(start_date, end_date))
+    }
+
+    pub fn daily_market_cap(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyMarketCap>>, AsyncError> {
+        self.block_on(self.inner.daily_market_cap(start_date, end_date))
+    }
+
+    pub fn erc20_token_total_supply(&self, token_contract_addr: &str) -> Result<u128, AsyncError> {
+        self.block_on(self.inner.erc20_token_total_supply(token_contract_addr))
+    }
+
+    pub fn erc20_token_balance_on_account(&self, account_addr: &str, token_contract_addr: &str) -> Result<u128, AsyncError> {
+        self.block_on(self.inner.erc20_token_balance_on_account(account_addr, token_contract_addr))
+    }
+
+    pub fn txs_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<Transaction>, AsyncError> {
+        self.block_on(self.inner.txs_on_account_from_to(account_addr, from_block, end_block))
+    }
+
+    pub fn txs_on_account(&self, account_addr: &str) -> Result<Vec<Transaction>, AsyncError> {
+        self.block_on(self.inner.txs_on_account(account_addr))
+    }
+
+    pub fn internal_txs_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<InternalTransaction>, AsyncError> {
+        self.block_on(self.inner.internal_txs_on_account_from_to(account_addr, from_block, end_block))
+    }
+
+    pub fn internal_txs_on_account(&self, addr: &str) -> Result<Vec<InternalTransaction>, AsyncError> {
+        self.block_on(self.inner.internal_txs_on_account(addr))
+    }
+
+    pub fn internal_txs_from_to(&self, from_block: u64, end_block: u64) -> Result<Vec<InternalTransaction>, AsyncError> {
+        self.block_on(self.inner.internal_txs_from_to(from_block, end_block))
+    }
+
+    pub fn internal_txs_by_tx_hash(&self, tx_hash: &str) -> Result<Vec<InternalTransaction>, AsyncError> {
+        self.block_on(self.inner.internal_txs_by_tx_hash(tx_hash))
+    }
+
+    pub fn erc20_transfers_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<ERC20TokenTransferEvent>, AsyncError> {
+        self.block_on(self.inner.erc20_transfers_on_account_from_to(account_addr, from_block, end_block))
+    }
+
+    pub fn erc20_transfer_events_on_account(&self, account_addr: &str) -> Result<Vec<ERC20TokenTransferEvent>, AsyncError> {
+        self.block_on(self.inner.erc20_transfer_events_on_account(account_addr))
+    }
+
+    pub fn erc20_transfers_on_account_by_contract(&self, account_addr: &str, token_contract_addr: &str) -> Result<Vec<ERC20TokenTransferEvent>, AsyncError> {
+        self.block_on(self.inner.erc20_transfers_on_account_by_contract(account_addr, token_contract_addr))
+    }
+
+    pub fn erc271_transfers_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<ERC721TokenTransferEvent>, AsyncError> {
+        self.block_on(self.inner.erc271_transfers_on_account_from_to(account_addr, from_block, end_block))
+    }
+
+    pub fn erc271_transfers_on_account(&self, account_addr: &str) -> Result<Vec<ERC721TokenTransferEvent>, AsyncError> {
+        self.block_on(self.inner.erc271_transfers_on_account(account_addr))
+    }
+
+    pub fn erc271_transfers_on_account_by_contract(&self, account_addr: &str, token_contract_addr: &str) -> Result<Vec<ERC721TokenTransferEvent>, AsyncError> {
+        self.block_on(self.inner.erc271_transfers_on_account_by_contract(account_addr, token_contract_addr))
+    }
+
+    pub fn account_activity<R: RangeBounds<u64>>(&self, account_addr: &str, blocks: R) -> Result<Vec<ActivityEntry>, AsyncError> {
+        self.block_on(self.inner.account_activity(account_addr, blocks))
+    }
+
+    pub fn contract_abi(&self, contract_addr: &str) -> Result<Abi, AsyncError> {
+        self.block_on(self.inner.contract_abi(contract_addr))
+    }
+
+    pub fn decode_input(&self, contract_addr: &str, input: &str) -> Result<DecodedCall, AsyncError> {
+        self.block_on(self.inner.decode_input(contract_addr, input))
+    }
+
+    pub fn logs(&self, filter: &LogFilter) -> Result<Vec<Log>, AsyncError> {
+        self.block_on(self.inner.logs(filter))
+    }
+
+    pub fn mined_blocks_by_account(&self, account_addr: &str) -> Result<Vec<MinedBlock>, AsyncError> {
+        self.block_on(self.inner.mined_blocks_by_account(account_addr))
+    }
+
+    pub fn contract_execution_status(&self, tx_hash: &str) -> Result<ContractExecutionStatus, AsyncError> {
+        self.block_on(self.inner.contract_execution_status(tx_hash))
+    }
+
+    pub fn tx_receipt_status(&self, tx_hash: &str) -> Result<TransactionReceiptStatus, AsyncError> {
+        self.block_on(self.inner.tx_receipt_status(tx_hash))
+    }
+
+    pub fn block_number(&self) -> Result<u64, AsyncError> {
+        self.block_on(self.inner.block_number())
+    }
+
+    pub fn tx_count(&self, account_addr: &str) -> Result<u64, AsyncError> {
+        self.block_on(self.inner.tx_count(account_addr))
+    }
+
+    pub fn tx_by_hash(&self, tx_hash: &str) -> Result<Option<ProxyTransaction>, AsyncError> {
+        self.block_on(self.inner.tx_by_hash(tx_hash))
+    }
+
+    pub fn tx_receipt(&self, tx_hash: &str) -> Result<Option<TransactionReceipt>, AsyncError> {
+        self.block_on(self.inner.tx_receipt(tx_hash))
+    }
+
+    pub fn wait_for_tx(&self, tx_hash: &str, confirmations: u64, timeout: Duration) -> Result<TxOutcome, AsyncError> {
+        self.block_on(self.inner.wait_for_tx(tx_hash, confirmations, timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::FixtureServer;
+    use super::*;
+
+    #[test]
+    fn queries_without_runtime() {
+        // the fixture server needs a runtime, the client doesn't
+        let server_runtime = Runtime::new().unwrap();
+        let server = server_runtime.enter(|| FixtureServer::start(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), Chain::Mainnet)).unwrap();
+        let api = API::new("test").with_base_url(&server.base_url()).with_rate_limit(20);
+        let supply = api.erc20_token_total_supply("0x514910771af9ca656af840dff83e8264ecf986ca").unwrap();
+        assert_eq!(supply, 1_000_000_000_000_000_000_000_000_000);
+        assert_eq!(api.block_number().unwrap(), 0xe3c000);
+        assert!(api.acc_balance("0x0000000000000000000000000000000000000000").is_err());
+    }
+}
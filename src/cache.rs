@@ -0,0 +1,294 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::{NaiveDate, Utc};
+use reqwest::Url;
+use serde_json::Value;
+
+use super::abi::keccak256;
+use super::format::parse_hex_or_dec;
+
+/// Actions whose results change with every block or are market data.
+const VOLATILE_ACTIONS: &[&str] = &[
+    "balance", "balancemulti", "tokenbalance", "gasoracle", "gasestimate", "ethprice", "ethsupply", "ethsupply2",
+    "tokensupply", "nodecount", "getstatus", "gettxreceiptstatus",
+];
+
+/// Actions returning records which carry `blockNumber` and `confirmations`.
+const BLOCK_RANGE_ACTIONS: &[&str] = &["txlist", "txlistinternal", "tokentx", "tokennfttx", "getLogs", "getminedblocks"];
+
+/// Stores raw response bodies by normalized request.
+pub trait Cache: Send + Sync {
+    fn get(&self, key: &str) -> Option<String>;
+
+    /// Stores the value, forever if `ttl` is `None`.
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>);
+
+    /// Drops an entry which turned out to be unusable. Without it, the entry is only replaced by
+    /// the next `set`.
+    fn remove(&self, _key: &str) {}
+}
+
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (String, Option<Instant>)>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> MemoryCache {
+        MemoryCache::default()
+    }
+}
+
+impl Cache for MemoryCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((_, Some(expires_at))) if *expires_at <= Instant::now() => {
+                entries.remove(key);
+                None
+            }
+            Some((value, _)) => Some(value.clone()),
+            None => None,
+        }
+    }
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.entries.lock().unwrap().insert(key.into(), (value.into(), expires_at));
+    }
+
+    fn remove(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// Keeps one file per entry in a directory. The first line of a file holds the unix time the entry
+/// expires at, or `-` if it never does.
+pub struct DiskCache {
+    dir: PathBuf,
+    // numbers the temporary files of concurrent writes within the process
+    writes: AtomicUsize,
+}
+
+impl DiskCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> std::io::Result<DiskCache> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(DiskCache { dir, writes: AtomicUsize::new(0) })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(hex::encode(keccak256(key.as_bytes())))
+    }
+}
+
+impl Cache for DiskCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key);
+        let content = fs::read_to_string(&path).ok()?;
+        let newline = content.find('\n')?;
+        let expires_at = &content[..newline];
+        if expires_at != "-" {
+            let expires_at: u64 = expires_at.parse().ok()?;
+            if expires_at <= unix_now() {
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+        }
+        Some(content[newline + 1..].into())
+    }
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) {
+        let expires_at = match ttl {
+            Some(ttl) => (unix_now() + ttl.as_secs()).to_string(),
+            None => "-".into(),
+        };
+        // the cache is best effort, a failed write only costs a refetch, but a crash while writing
+        // must not leave a truncated entry behind
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("{}-{}.tmp", std::process::id(), self.writes.fetch_add(1, Ordering::Relaxed)));
+        if fs::write(&tmp, format!("{}\n{}", expires_at, value)).is_err() || fs::rename(&tmp, &path).is_err() {
+            let _ = fs::remove_file(tmp);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path(key));
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Decides how long a response may be cached.
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    /// TTL for balances, the gas oracle, prices and other values which change with every block.
+    pub volatile_ttl: Duration,
+    /// TTL for everything which is neither volatile nor known to be final.
+    pub default_ttl: Duration,
+    /// The number of confirmations after which a block is treated as final.
+    pub finality_depth: u64,
+}
+
+impl Default for CachePolicy {
+    fn default() -> CachePolicy {
+        CachePolicy { volatile_ttl: Duration::from_secs(15), default_ttl: Duration::from_secs(300), finality_depth: 64 }
+    }
+}
+
+impl CachePolicy {
+    /// Returns the TTL for the response body of the request, where `Some(None)` means forever.
+    /// `head_block` is the latest block number known so far.
+    pub(crate) fn ttl(&self, uri: &str, body: &str, head_block: u64) -> Option<Option<Duration>> {
+        let params = query_params(uri)?;
+        let action = params.get("action").map(String::as_str).unwrap_or("");
+        if VOLATILE_ACTIONS.contains(&action) {
+            return Some(Some(self.volatile_ttl));
+        }
+        let finalized_block = head_block.saturating_sub(self.finality_depth);
+        if BLOCK_RANGE_ACTIONS.contains(&action) {
+            // a tx hash lookup is final once the tx is buried deep enough
+            if params.contains_key("txhash") {
+                let records = records(body);
+                if !records.is_empty() && records.iter().all(|(block, _)| *block <= finalized_block) {
+                    return Some(None);
+                }
+                return Some(Some(self.default_ttl));
+            }
+            let end_block = params.get("endblock").or_else(|| params.get("toBlock")).and_then(|b| b.parse::<u64>().ok());
+            return match end_block {
+                Some(end_block) if head_block > 0 && end_block <= finalized_block => Some(None),
+                _ => Some(Some(self.default_ttl)),
+            };
+        }
+        let end_date = params.get("enddate").and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        match end_date {
+            Some(end_date) if end_date < Utc::now().naive_utc().date() => Some(None),
+            _ => Some(Some(self.default_ttl)),
+        }
+    }
+}
+
+/// The latest block number a response reveals, either through `blockNumber + confirmations`
+/// of its records or through the gas oracle's `LastBlock`.
+pub(crate) fn head_block(body: &str) -> u64 {
+    let value: Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(_) => return 0,
+    };
+    let from_records = records_of(&value).iter()
+        .filter_map(|(block, confirmations)| confirmations.map(|c| block + c.saturating_sub(1)))
+        .max()
+        .unwrap_or(0);
+    let from_oracle = value["result"]["LastBlock"].as_str().and_then(|b| b.parse().ok()).unwrap_or(0);
+    from_records.max(from_oracle)
+}
+
+fn records(body: &str) -> Vec<(u64, Option<u64>)> {
+    serde_json::from_str::<Value>(body).map(|value| records_of(&value)).unwrap_or_default()
+}
+
+fn records_of(value: &Value) -> Vec<(u64, Option<u64>)> {
+    let number = |v: &Value| v.as_str().and_then(|s| parse_hex_or_dec(s).ok()).map(|n| n as u64);
+    match value["result"].as_array() {
+        Some(records) => records.iter()
+            .filter_map(|r| Some((number(&r["blockNumber"])?, number(&r["confirmations"]))))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn query_params(uri: &str) -> Option<BTreeMap<String, String>> {
+    let url = Url::parse(uri).ok()?;
+    Some(url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect())
+}
+
+/// The request with its parameters sorted and the API key stripped.
+pub(crate) fn cache_key(uri: &str) -> String {
+    match Url::parse(uri) {
+        Ok(url) => format!("{}{}?{}", url.host_str().unwrap_or(""), url.path().trim_end_matches('/'), normalized_query(url.query().unwrap_or(""))),
+        Err(_) => uri.into(),
+    }
+}
+
+/// The query string with its parameters sorted and the API key stripped.
+pub(crate) fn normalized_query(query: &str) -> String {
+    let mut url = Url::parse("http://localhost").expect("static URL is valid");
+    url.set_query(Some(query));
+    let params: BTreeMap<String, String> = url.query_pairs()
+        .filter(|(k, _)| k != "apikey")
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    let params: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    params.join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_strips_api_key_and_normalizes() {
+        assert_eq!(cache_key("https://api.etherscan.io/api/?module=account&apikey=SECRET&action=balance"),
+                   cache_key("https://api.etherscan.io/api?action=balance&module=account&apikey=OTHER"));
+        assert!(!cache_key("https://api.etherscan.io/api?module=account&apikey=SECRET").contains("SECRET"));
+    }
+
+    #[test]
+    fn ttl_by_request_kind() {
+        let policy = CachePolicy::default();
+        let body = r#"{"status":"1","message":"OK","result":[{"blockNumber":"100","confirmations":"1000"}]}"#;
+        let head = head_block(body);
+        assert_eq!(head, 1099);
+
+        let balance = "https://api.etherscan.io/api?module=account&action=balance&address=0x1&tag=latest";
+        assert_eq!(policy.ttl(balance, "", head), Some(Some(policy.volatile_ttl)));
+
+        let final_range = "https://api.etherscan.io/api?module=account&action=txlist&address=0x1&startblock=0&endblock=1000";
+        assert_eq!(policy.ttl(final_range, body, head), Some(None));
+
+        let open_range = "https://api.etherscan.io/api?module=account&action=txlist&address=0x1&startblock=0&endblock=1090";
+        assert_eq!(policy.ttl(open_range, body, head), Some(Some(policy.default_ttl)));
+
+        let unbounded = "https://api.etherscan.io/api?module=account&action=txlist&address=0x1";
+        assert_eq!(policy.ttl(unbounded, body, head), Some(Some(policy.default_ttl)));
+
+        let by_hash = "https://api.etherscan.io/api?module=account&action=txlistinternal&txhash=0x2";
+        assert_eq!(policy.ttl(by_hash, body, head), Some(None));
+
+        let past_days = "https://api.etherscan.io/api?module=stats&action=dailytx&startdate=2019-02-01&enddate=2019-02-28";
+        assert_eq!(policy.ttl(past_days, "", head), Some(None));
+    }
+
+    #[test]
+    fn memory_cache_expires() {
+        let cache = MemoryCache::new();
+        cache.set("a", "1", None);
+        cache.set("b", "2", Some(Duration::from_secs(0)));
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("b"), None);
+    }
+
+    #[test]
+    fn disk_cache_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("etherscanio-cache-test-{}", std::process::id()));
+        let cache = DiskCache::new(&dir).unwrap();
+        cache.set("a", "{\"status\":\"1\"}\n", None);
+        cache.set("b", "2", Some(Duration::from_secs(0)));
+        assert_eq!(cache.get("a").as_deref(), Some("{\"status\":\"1\"}\n"));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), None);
+        cache.remove("a");
+        assert_eq!(cache.get("a"), None);
+        // the expired and the removed entry are gone and no temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
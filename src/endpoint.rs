@@ -0,0 +1,76 @@
+//! Describing Etherscan actions, so that those the crate has no method for can be called
+//! through `API::call`.
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use super::request::Request;
+
+/// An Etherscan action with its parameters. The API key is added by the client.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// use etherscanio::{Endpoint, API};
+///
+/// struct BlockReward(u64);
+///
+/// impl Endpoint for BlockReward {
+///     type Response = serde_json::Value;
+///
+///     fn module(&self) -> &str { "block" }
+///
+///     fn action(&self) -> &str { "getblockreward" }
+///
+///     fn params(&self) -> Vec<(String, String)> {
+///         vec![("blockno".into(), self.0.to_string())]
+///     }
+/// }
+///
+/// let reward = API::new_from_env()?.call(BlockReward(2165403)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Endpoint {
+    /// What the `result` of the response deserializes into. Proxy actions answer in JSON-RPC
+    /// format, their `result` is used the same way.
+    type Response: DeserializeOwned + Debug + Send + Sync + 'static;
+
+    fn module(&self) -> &str;
+
+    fn action(&self) -> &str;
+
+    /// The parameters besides module and action.
+    fn params(&self) -> Vec<(String, String)>;
+}
+
+/// A request whose result deserializes into `T`, the endpoint of the built-in methods.
+pub(crate) struct Call<T> {
+    request: Request,
+    response: PhantomData<fn() -> T>,
+}
+
+impl<T> Call<T> {
+    pub(crate) fn new(request: Request) -> Call<T> {
+        Call { request, response: PhantomData }
+    }
+}
+
+impl<T> Endpoint for Call<T>
+    where T: DeserializeOwned + Debug + Send + Sync + 'static
+{
+    type Response = T;
+
+    fn module(&self) -> &str {
+        self.request.module()
+    }
+
+    fn action(&self) -> &str {
+        self.request.action()
+    }
+
+    fn params(&self) -> Vec<(String, String)> {
+        self.request.params().to_vec()
+    }
+}
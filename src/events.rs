@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use primitive_types::U256;
+
+use super::abi::{Event, Token};
+use super::models::Log;
+
+const ERC20_TRANSFER: &str = "Transfer(address indexed from, address indexed to, uint256 value)";
+const ERC721_TRANSFER: &str = "Transfer(address indexed from, address indexed to, uint256 indexed tokenId)";
+const ERC20_APPROVAL: &str = "Approval(address indexed owner, address indexed spender, uint256 value)";
+const ERC721_APPROVAL: &str = "Approval(address indexed owner, address indexed approved, uint256 indexed tokenId)";
+const APPROVAL_FOR_ALL: &str = "ApprovalForAll(address indexed owner, address indexed operator, bool approved)";
+const TRANSFER_SINGLE: &str = "TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value)";
+const TRANSFER_BATCH: &str = "TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values)";
+
+/// The standard events by signature with their first topic, parsed and hashed once. The events are
+/// marked anonymous, as their first topic is matched before decoding.
+fn token_events() -> &'static HashMap<&'static str, ([u8; 32], Event)> {
+    static EVENTS: OnceLock<HashMap<&'static str, ([u8; 32], Event)>> = OnceLock::new();
+    EVENTS.get_or_init(|| {
+        [ERC20_TRANSFER, ERC721_TRANSFER, ERC20_APPROVAL, ERC721_APPROVAL, APPROVAL_FOR_ALL, TRANSFER_SINGLE, TRANSFER_BATCH].iter()
+            .map(|signature| {
+                let event = Event::parse(signature).expect("token event signatures are valid");
+                (*signature, (event.topic(), Event { anonymous: true, ..event }))
+            })
+            .collect()
+    })
+}
+
+/// What a `Transfer` or `Approval` event moves: an ERC20 amount or an ERC721 token id.
+/// Both standards share the event signatures and only differ in whether the last parameter is indexed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenValue {
+    Amount(U256),
+    TokenId(U256),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+    pub from: String,
+    pub to: String,
+    pub value: TokenValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Approval {
+    pub owner: String,
+    pub spender: String,
+    pub value: TokenValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferSingle {
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub id: U256,
+    pub value: U256,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferBatch {
+    pub operator: String,
+    pub from: String,
+    pub to: String,
+    pub ids: Vec<U256>,
+    pub values: Vec<U256>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalForAll {
+    pub owner: String,
+    pub operator: String,
+    pub approved: bool,
+}
+
+/// An ERC20, ERC721 or ERC1155 event decoded from a log.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenEvent {
+    Transfer(Transfer),
+    Approval(Approval),
+    TransferSingle(TransferSingle),
+    TransferBatch(TransferBatch),
+    ApprovalForAll(ApprovalForAll),
+}
+
+impl TokenEvent {
+    /// Decodes the log if it is one of the standard token events, regardless of whether the emitting
+    /// contract is classified as a token by Etherscan.
+    pub fn from_log(log: &Log) -> Option<TokenEvent> {
+        let (topics, data) = log.raw().ok()?;
+        let topic0 = topics.first()?;
+        let decode = |signature: &str| {
+            let (topic, event) = &token_events()[signature];
+            if topic != topic0 {
+                return None;
+            }
+            event.decode_log(&topics[1..], &data).ok()
+        };
+
+        if let Some(l) = decode(ERC20_TRANSFER) {
+            return Some(TokenEvent::Transfer(Transfer { from: address(&l.params[0].1)?, to: address(&l.params[1].1)?, value: TokenValue::Amount(l.params[2].1.as_uint()?) }));
+        }
+        if let Some(l) = decode(ERC721_TRANSFER) {
+            return Some(TokenEvent::Transfer(Transfer { from: address(&l.params[0].1)?, to: address(&l.params[1].1)?, value: TokenValue::TokenId(l.params[2].1.as_uint()?) }));
+        }
+        if let Some(l) = decode(ERC20_APPROVAL) {
+            return Some(TokenEvent::Approval(Approval { owner: address(&l.params[0].1)?, spender: address(&l.params[1].1)?, value: TokenValue::Amount(l.params[2].1.as_uint()?) }));
+        }
+        if let Some(l) = decode(ERC721_APPROVAL) {
+            return Some(TokenEvent::Approval(Approval { owner: address(&l.params[0].1)?, spender: address(&l.params[1].1)?, value: TokenValue::TokenId(l.params[2].1.as_uint()?) }));
+        }
+        if let Some(l) = decode(APPROVAL_FOR_ALL) {
+            return Some(TokenEvent::ApprovalForAll(ApprovalForAll { owner: address(&l.params[0].1)?, operator: address(&l.params[1].1)?, approved: l.params[2].1.as_bool()? }));
+        }
+        if let Some(l) = decode(TRANSFER_SINGLE) {
+            return Some(TokenEvent::TransferSingle(TransferSingle {
+                operator: address(&l.params[0].1)?,
+                from: address(&l.params[1].1)?,
+                to: address(&l.params[2].1)?,
+                id: l.params[3].1.as_uint()?,
+                value: l.params[4].1.as_uint()?,
+            }));
+        }
+        if let Some(l) = decode(TRANSFER_BATCH) {
+            return Some(TokenEvent::TransferBatch(TransferBatch {
+                operator: address(&l.params[0].1)?,
+                from: address(&l.params[1].1)?,
+                to: address(&l.params[2].1)?,
+                ids: uints(&l.params[3].1)?,
+                values: uints(&l.params[4].1)?,
+            }));
+        }
+        None
+    }
+}
+
+fn address(token: &Token) -> Option<String> {
+    token.as_address().map(String::from)
+}
+
+fn uints(token: &Token) -> Option<Vec<U256>> {
+    match token {
+        Token::Array(tokens) => tokens.iter().map(Token::as_uint).collect(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+    const FROM_TOPIC: &str = "0x0000000000000000000000004e83362442b8d1bec281594cea3050c8eb01311c";
+    const TO_TOPIC: &str = "0x000000000000000000000000d8da6bf26964af9d7eed9e03e53415d37aa96045";
+
+    fn log(topics: &[&str], data: &str) -> Log {
+        serde_json::from_value(serde_json::json!({
+            "address": "0x514910771af9ca656af840dff83e8264ecf986ca",
+            "topics": topics,
+            "data": data,
+            "blockNumber": "0xc48174",
+            "timeStamp": "0x607b4b3c",
+            "gasPrice": "0x2540be400",
+            "gasUsed": "0x1a6ec",
+            "logIndex": "0x",
+            "transactionHash": "0x0b03498648ae2da924f961dda00dc6bb0a8df15519262b7e012b7d67f4bb7e83",
+            "transactionIndex": "0x2",
+        })).unwrap()
+    }
+
+    #[test]
+    fn erc20_and_erc721_transfers() {
+        let erc20 = log(&[TRANSFER_TOPIC, FROM_TOPIC, TO_TOPIC], "0x0000000000000000000000000000000000000000000000000de0b6b3a7640000");
+        assert_eq!(erc20.log_index(), 0);
+        assert_eq!(erc20.block_number(), 12878196);
+        assert_eq!(TokenEvent::from_log(&erc20), Some(TokenEvent::Transfer(Transfer {
+            from: "0x4e83362442b8d1bec281594cea3050c8eb01311c".into(),
+            to: "0xd8da6bf26964af9d7eed9e03e53415d37aa96045".into(),
+            value: TokenValue::Amount(U256::from(1_000_000_000_000_000_000u64)),
+        })));
+
+        let token_id = "0x000000000000000000000000000000000000000000000000000000000000002a";
+        let erc721 = log(&[TRANSFER_TOPIC, FROM_TOPIC, TO_TOPIC, token_id], "0x");
+        match TokenEvent::from_log(&erc721) {
+            Some(TokenEvent::Transfer(transfer)) => assert_eq!(transfer.value, TokenValue::TokenId(U256::from(42))),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn erc1155_transfer_batch() {
+        let topic = format!("0x{}", hex::encode(Event::parse(TRANSFER_BATCH).unwrap().topic()));
+        let data = concat!(
+            "0x",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "000000000000000000000000000000000000000000000000000000000000000a",
+            "0000000000000000000000000000000000000000000000000000000000000014",
+        );
+        let batch = log(&[&topic, FROM_TOPIC, FROM_TOPIC, TO_TOPIC], data);
+        match TokenEvent::from_log(&batch) {
+            Some(TokenEvent::TransferBatch(batch)) => {
+                assert_eq!(batch.ids, vec![U256::from(1), U256::from(2)]);
+                assert_eq!(batch.values, vec![U256::from(10), U256::from(20)]);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_event() {
+        let unknown = log(&["0x0000000000000000000000000000000000000000000000000000000000000001"], "0x");
+        assert_eq!(TokenEvent::from_log(&unknown), None);
+    }
+}
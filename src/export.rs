@@ -0,0 +1,444 @@
+//! Export of list results to CSV or JSON Lines, e.g. for spreadsheets.
+//!
+//! Columns use Etherscan's field names in the order Etherscan returns them, followed by computed
+//! columns: the UTC date time, the direction relative to the queried address, human-readable
+//! amounts and, where the row belongs to a transaction, the fee. The fee is only filled in where
+//! the queried address paid it, and only in the first row of its tx, so the column can be summed.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+
+use chrono::{TimeZone, Utc};
+use primitive_types::U256;
+
+use super::models::{ChainSize, ERC20TokenTransferEvent, ERC721TokenTransferEvent, InternalTransaction, Log, MinedBlock, ReceiptStatus, Transaction};
+use super::units::{format_ether, format_units_u256, ETHER_DECIMALS};
+
+/// The fields of a record as Etherscan names and formats them, all as strings.
+pub trait EtherscanFields {
+    const FIELDS: &'static [&'static str];
+
+    fn field_values(&self) -> Vec<String>;
+}
+
+/// A row of an export.
+pub trait Record {
+    /// The column names, the same for every row.
+    fn columns() -> Vec<&'static str>;
+
+    /// The row's values in column order. `address` is the queried account, the `direction` and
+    /// fee columns are left empty without it. `fees` holds the txs whose fee earlier rows of the
+    /// export carry.
+    fn values(&self, address: Option<&str>, fees: &mut Fees) -> Vec<String>;
+}
+
+/// The txs whose fee is part of an export already, so that a tx's fee is counted once however
+/// many rows the tx has.
+#[derive(Debug, Default)]
+pub struct Fees {
+    charged: HashSet<String>,
+}
+
+impl Fees {
+    pub fn new() -> Fees {
+        Fees::default()
+    }
+
+    // the `fee` and `feeEth` columns, empty unless the account sent the tx and the fee is new
+    fn columns(&mut self, hash: &str, direction: Option<Direction>, fee: Option<U256>) -> Vec<String> {
+        match (direction, fee) {
+            (Some(Direction::Out), Some(fee)) | (Some(Direction::SelfTransfer), Some(fee)) if self.charged.insert(hash.to_lowercase()) =>
+                vec![fee.to_string(), format_units_u256(fee, ETHER_DECIMALS)],
+            _ => vec![String::new(), String::new()],
+        }
+    }
+}
+
+/// The direction of a transfer relative to an account.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    In,
+    Out,
+    /// The account sent to itself.
+    SelfTransfer,
+}
+
+impl Direction {
+    /// Returns `None` if the account is neither sender nor recipient.
+    pub fn of(from: &str, to: Option<&str>, address: &str) -> Option<Direction> {
+        let is_sender = from.eq_ignore_ascii_case(address);
+        let is_recipient = to.is_some_and(|to| to.eq_ignore_ascii_case(address));
+        match (is_sender, is_recipient) {
+            (true, true) => Some(Direction::SelfTransfer),
+            (true, false) => Some(Direction::Out),
+            (false, true) => Some(Direction::In),
+            (false, false) => None,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::In => write!(f, "IN"),
+            Direction::Out => write!(f, "OUT"),
+            Direction::SelfTransfer => write!(f, "SELF"),
+        }
+    }
+}
+
+/// Writes a header line and one line per record.
+pub fn write_csv<'a, W, R, I>(mut writer: W, records: I, address: Option<&str>) -> io::Result<()>
+    where W: Write, R: Record + 'a, I: IntoIterator<Item = &'a R> {
+    writeln!(writer, "{}", csv_line(R::columns().into_iter().map(String::from)))?;
+    let mut fees = Fees::new();
+    for record in records {
+        writeln!(writer, "{}", csv_line(record.values(address, &mut fees).into_iter()))?;
+    }
+    writer.flush()
+}
+
+/// Writes one JSON object per line and record, with all values as strings like Etherscan returns them.
+pub fn write_jsonl<'a, W, R, I>(mut writer: W, records: I, address: Option<&str>) -> io::Result<()>
+    where W: Write, R: Record + 'a, I: IntoIterator<Item = &'a R> {
+    let mut fees = Fees::new();
+    for record in records {
+        // written by hand, a serde_json::Map would sort the columns
+        let fields: Vec<String> = R::columns().into_iter().zip(record.values(address, &mut fees))
+            .map(|(column, value)| format!("{}:{}", json_string(column), json_string(&value)))
+            .collect();
+        writeln!(writer, "{{{}}}", fields.join(","))?;
+    }
+    writer.flush()
+}
+
+fn csv_line<I: Iterator<Item = String>>(fields: I) -> String {
+    fields.map(|field| {
+        if field.contains(&[',', '"', '\n', '\r'][..]) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field
+        }
+    }).collect::<Vec<_>>().join(",")
+}
+
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).expect("strings always serialize")
+}
+
+fn date_time(timestamp: u64) -> String {
+    Utc.timestamp_opt(timestamp as i64, 0).single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn direction(from: &str, to: Option<&str>, address: Option<&str>) -> Option<Direction> {
+    address.and_then(|address| Direction::of(from, to, address))
+}
+
+fn direction_string(direction: Option<Direction>) -> String {
+    direction.map(|d| d.to_string()).unwrap_or_default()
+}
+
+fn flag(value: bool) -> String {
+    if value { "1".into() } else { "0".into() }
+}
+
+impl EtherscanFields for Transaction {
+    const FIELDS: &'static [&'static str] = &["blockNumber", "timeStamp", "hash", "nonce", "blockHash", "transactionIndex",
+        "from", "to", "value", "gas", "gasPrice", "isError", "txreceipt_status", "input", "contractAddress",
+        "cumulativeGasUsed", "gasUsed", "confirmations"];
+
+    fn field_values(&self) -> Vec<String> {
+        let receipt_status = match self.receipt_status() {
+            Some(ReceiptStatus::Pass) => "1".into(),
+            Some(ReceiptStatus::Fail { .. }) => "0".into(),
+            None => String::new(),
+        };
+        vec![
+            self.block_number().to_string(),
+            self.timestamp().to_string(),
+            self.hash().into(),
+            self.nonce().to_string(),
+            self.block_hash().into(),
+            self.transaction_index().to_string(),
+            self.from().into(),
+            self.to().unwrap_or("").into(),
+            self.value().to_string(),
+            self.gas().to_string(),
+            self.gas_price().to_string(),
+            flag(self.is_error()),
+            receipt_status,
+            self.input().into(),
+            self.contract_address().unwrap_or("").into(),
+            self.cumulative_gas_used().to_string(),
+            self.gas_used().map(|g| g.to_string()).unwrap_or_default(),
+            self.confirmations().to_string(),
+        ]
+    }
+}
+
+impl Record for Transaction {
+    fn columns() -> Vec<&'static str> {
+        [Self::FIELDS, &["dateTime", "direction", "valueEth", "fee", "feeEth"]].concat()
+    }
+
+    fn values(&self, address: Option<&str>, fees: &mut Fees) -> Vec<String> {
+        let direction = direction(self.from(), self.to(), address);
+        let mut values = self.field_values();
+        values.extend(vec![date_time(self.timestamp()), direction_string(direction), format_ether(self.value())]);
+        values.extend(fees.columns(self.hash(), direction, self.fee()));
+        values
+    }
+}
+
+impl EtherscanFields for InternalTransaction {
+    const FIELDS: &'static [&'static str] = &["blockNumber", "timeStamp", "hash", "from", "to", "value", "contractAddress",
+        "input", "type", "gas", "gasUsed", "traceId", "isError", "errCode"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![
+            self.block_number().to_string(),
+            self.timestamp().to_string(),
+            self.hash().into(),
+            self.from().into(),
+            self.to().unwrap_or("").into(),
+            self.value().to_string(),
+            self.contract_address().unwrap_or("").into(),
+            self.input().into(),
+            self.tx_type().into(),
+            self.gas().to_string(),
+            self.gas_used().map(|g| g.to_string()).unwrap_or_default(),
+            self.trace_id().to_string(),
+            flag(self.is_error()),
+            self.err_code().unwrap_or("").into(),
+        ]
+    }
+}
+
+impl Record for InternalTransaction {
+    fn columns() -> Vec<&'static str> {
+        [Self::FIELDS, &["dateTime", "direction", "valueEth"]].concat()
+    }
+
+    fn values(&self, address: Option<&str>, _fees: &mut Fees) -> Vec<String> {
+        let mut values = self.field_values();
+        values.extend(vec![
+            date_time(self.timestamp()),
+            direction_string(direction(self.from(), self.to(), address)),
+            format_ether(self.value()),
+        ]);
+        values
+    }
+}
+
+impl EtherscanFields for ERC20TokenTransferEvent {
+    const FIELDS: &'static [&'static str] = &["blockNumber", "timeStamp", "hash", "nonce", "blockHash", "from",
+        "contractAddress", "to", "value", "tokenName", "tokenSymbol", "tokenDecimal", "transactionIndex", "logIndex",
+        "gas", "gasPrice", "gasUsed", "cumulativeGasUsed", "input", "confirmations"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![
+            self.block_number().to_string(),
+            self.timestamp().to_string(),
+            self.hash().into(),
+            self.nonce().to_string(),
+            self.block_hash().into(),
+            self.from().into(),
+            self.contract_address().into(),
+            self.to().into(),
+            self.value().to_string(),
+            self.token_name().into(),
+            self.token_symbol().into(),
+            self.token_decimal().to_string(),
+            self.transaction_index().to_string(),
+            self.log_index().map(|i| i.to_string()).unwrap_or_default(),
+            self.gas().to_string(),
+            self.gas_price().to_string(),
+            self.gas_used().map(|g| g.to_string()).unwrap_or_default(),
+            self.cumulative_gas_used().to_string(),
+            self.input().into(),
+            self.confirmations().to_string(),
+        ]
+    }
+}
+
+impl Record for ERC20TokenTransferEvent {
+    fn columns() -> Vec<&'static str> {
+        [Self::FIELDS, &["dateTime", "direction", "amount", "fee", "feeEth"]].concat()
+    }
+
+    fn values(&self, address: Option<&str>, fees: &mut Fees) -> Vec<String> {
+        let direction = direction(self.from(), Some(self.to()), address);
+        let mut values = self.field_values();
+        values.extend(vec![date_time(self.timestamp()), direction_string(direction), self.amount().to_decimal_string()]);
+        values.extend(fees.columns(self.hash(), direction, self.fee()));
+        values
+    }
+}
+
+impl EtherscanFields for ERC721TokenTransferEvent {
+    const FIELDS: &'static [&'static str] = ERC20TokenTransferEvent::FIELDS;
+
+    fn field_values(&self) -> Vec<String> {
+        (**self).field_values()
+    }
+}
+
+impl Record for ERC721TokenTransferEvent {
+    fn columns() -> Vec<&'static str> {
+        ERC20TokenTransferEvent::columns()
+    }
+
+    fn values(&self, address: Option<&str>, fees: &mut Fees) -> Vec<String> {
+        (**self).values(address, fees)
+    }
+}
+
+impl EtherscanFields for Log {
+    /// `topics` holds a JSON array.
+    const FIELDS: &'static [&'static str] = &["address", "topics", "data", "blockNumber", "timeStamp", "gasPrice",
+        "gasUsed", "logIndex", "transactionHash", "transactionIndex"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![
+            self.address().into(),
+            serde_json::to_string(self.topics()).expect("strings always serialize"),
+            self.data().into(),
+            self.block_number().to_string(),
+            self.timestamp().to_string(),
+            self.gas_price().to_string(),
+            self.gas_used().to_string(),
+            self.log_index().to_string(),
+            self.transaction_hash().into(),
+            self.transaction_index().to_string(),
+        ]
+    }
+}
+
+impl Record for Log {
+    fn columns() -> Vec<&'static str> {
+        [Self::FIELDS, &["dateTime"]].concat()
+    }
+
+    fn values(&self, _address: Option<&str>, _fees: &mut Fees) -> Vec<String> {
+        let mut values = self.field_values();
+        values.push(date_time(self.timestamp()));
+        values
+    }
+}
+
+impl EtherscanFields for MinedBlock {
+    const FIELDS: &'static [&'static str] = &["blockNumber", "timeStamp", "blockReward"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.block_number().to_string(), self.timestamp().to_string(), self.block_rewards().to_string()]
+    }
+}
+
+impl Record for MinedBlock {
+    fn columns() -> Vec<&'static str> {
+        [Self::FIELDS, &["dateTime", "blockRewardEth"]].concat()
+    }
+
+    fn values(&self, _address: Option<&str>, _fees: &mut Fees) -> Vec<String> {
+        let mut values = self.field_values();
+        values.extend(vec![date_time(self.timestamp()), format_ether(self.block_rewards())]);
+        values
+    }
+}
+
+impl EtherscanFields for ChainSize {
+    const FIELDS: &'static [&'static str] = &["blockNumber", "chainTimeStamp", "chainSize", "clientType", "syncMode"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.block_number.to_string(), self.chain_date.to_string(), self.chain_size.to_string(), self.client_type.clone(), self.sync_mode.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction() -> Transaction {
+        serde_json::from_str(r#"{"blockNumber":"14923678","timeStamp":"1654646411","hash":"0xc52783ad354aecc04c670047754f062e3d6d04e8f5b24774472651f9c3882c60","nonce":"1","blockHash":"0x7e1638fd2c6bdd05ffd83c1cf06c63e2f67d0f802084bef076d06bdcf86d1bb0","transactionIndex":"61","from":"0x9aa99c23f67c81701c772b106b4f83f6e858dd2e","to":"0xC5102fe9359fd9a28f877a67e36b0f050d81a3cc","value":"1500000000000000000","gas":"60000","gasPrice":"20000000000","isError":"0","txreceipt_status":"1","input":"0x","contractAddress":"","cumulativeGasUsed":"4457269","gasUsed":"21000","confirmations":"122485"}"#).unwrap()
+    }
+
+    #[test]
+    fn csv_columns_and_computed_values() {
+        let mut out = vec![];
+        write_csv(&mut out, &[transaction()], Some("0x9AA99C23F67C81701C772B106B4F83F6E858DD2E")).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], Transaction::columns().join(","));
+        assert!(lines[1].starts_with("14923678,1654646411,0xc527"));
+        assert!(lines[1].ends_with(",2022-06-08 00:00:11,OUT,1.5,420000000000000,0.00042"));
+    }
+
+    #[test]
+    fn jsonl_keeps_column_order() {
+        let mut out = vec![];
+        write_jsonl(&mut out, &[transaction(), transaction()], Some("0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc")).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"blockNumber":"14923678","timeStamp":"1654646411","hash":"#));
+        let row: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(row["direction"], "IN");
+        assert_eq!(row["txreceipt_status"], "1");
+        assert_eq!(row["contractAddress"], "");
+    }
+
+    #[test]
+    fn fills_fee_once_where_the_account_paid_it() {
+        let path = format!("{}/fixtures/account_tokentx_74ba0314.json", env!("CARGO_MANIFEST_DIR"));
+        let fixture: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        // both transfers belong to the same tx
+        let transfers: Vec<ERC20TokenTransferEvent> = serde_json::from_value(fixture["response"]["result"].clone()).unwrap();
+        let sender = transfers[0].from().to_string();
+        let rows = |address: &str| -> Vec<serde_json::Value> {
+            let mut out = vec![];
+            write_jsonl(&mut out, &transfers, Some(address)).unwrap();
+            String::from_utf8(out).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+        };
+        let sent = rows(&sender);
+        assert_eq!(sent[0]["fee"], transfers[0].fee().unwrap().to_string());
+        assert_eq!(sent[1]["fee"], "");
+        let received = rows(transfers[0].to());
+        assert!(received.iter().all(|row| row["fee"] == "" && row["feeEth"] == ""));
+    }
+
+    #[test]
+    fn exports_logs_and_mined_blocks() {
+        let result = |name: &str| {
+            let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+            let fixture: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            fixture["response"]["result"].clone()
+        };
+        let logs: Vec<Log> = serde_json::from_value(result("logs_getLogs_1ce0bf5f.json")).unwrap();
+        let mut out = vec![];
+        write_csv(&mut out, &logs, None).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), logs.len() + 1);
+
+        let blocks: Vec<MinedBlock> = serde_json::from_value(result("account_getminedblocks_de91d2f7.json")).unwrap();
+        let mut out = vec![];
+        write_jsonl(&mut out, &blocks, None).unwrap();
+        let row: serde_json::Value = serde_json::from_str(String::from_utf8(out).unwrap().lines().next().unwrap()).unwrap();
+        assert_eq!(row["blockRewardEth"], format_ether(blocks[0].block_rewards()));
+    }
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(csv_line(vec!["a".to_string(), "b,c".into(), "d\"e".into()].into_iter()), "a,\"b,c\",\"d\"\"e\"");
+    }
+
+    #[test]
+    fn direction_of_transfer() {
+        assert_eq!(Direction::of("0xA", Some("0xb"), "0xa"), Some(Direction::Out));
+        assert_eq!(Direction::of("0xa", Some("0xB"), "0xb"), Some(Direction::In));
+        assert_eq!(Direction::of("0xa", Some("0xa"), "0xA"), Some(Direction::SelfTransfer));
+        assert_eq!(Direction::of("0xa", None, "0xb"), None);
+    }
+}
@@ -1,7 +1,11 @@
+use std::convert::TryFrom;
 use std::fmt::Display;
 use std::str::FromStr;
 
-use serde::{de, Deserialize, Deserializer};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use super::units::parse_gwei;
 
 pub fn from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
     where T: FromStr,
@@ -10,4 +14,199 @@ pub fn from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 {
     let s = String::deserialize(deserializer)?;
     T::from_str(&s).map_err(de::Error::custom)
-}
\ No newline at end of file
+}
+
+/// Serializes a number as string, the way Etherscan sends it. Unlike a number, the string fits into a
+/// `serde_json::Value` even for `u128`.
+pub fn to_str<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Display,
+          S: Serializer
+{
+    serializer.collect_str(value)
+}
+
+pub fn to_optional_str<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Display,
+          S: Serializer
+{
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserializes the `"0"`/`"1"` flags used by Etherscan into a bool.
+pub fn from_bool_str<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    where D: Deserializer<'de>
+{
+    let s = String::deserialize(deserializer)?;
+    match s.as_str() {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(de::Error::custom(format!("invalid flag '{}'", s))),
+    }
+}
+
+/// Like `from_str` but for fields which are absent or blank on some records.
+pub fn from_optional_str<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where T: FromStr,
+          T::Err: Display,
+          D: Deserializer<'de>
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(s) if !s.is_empty() => T::from_str(&s).map(Some).map_err(de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+/// Deserializes a number given either as `0x` prefixed hex string or as decimal string, as the proxy module
+/// and the account module respectively return them.
+pub fn from_hex_or_dec_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: TryFrom<u128>,
+          T::Error: Display,
+          D: Deserializer<'de>
+{
+    let s = String::deserialize(deserializer)?;
+    parse_hex_or_dec(&s)
+        .map_err(de::Error::custom)
+        .and_then(|v| T::try_from(v).map_err(de::Error::custom))
+}
+
+/// Like `from_hex_or_dec_str` but treats absent and blank values as `None`.
+pub fn from_optional_hex_or_dec_str<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where T: TryFrom<u128>,
+          T::Error: Display,
+          D: Deserializer<'de>
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(s) if !s.is_empty() => parse_hex_or_dec(&s)
+            .map_err(de::Error::custom)
+            .and_then(|v| T::try_from(v).map_err(de::Error::custom))
+            .map(Some),
+        _ => Ok(None),
+    }
+}
+
+pub fn parse_hex_or_dec(s: &str) -> Result<u128, String> {
+    let result = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        // the logs module sends a bare "0x" for zero
+        Some("") => Ok(0),
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    result.map_err(|e| format!("invalid number '{}': {}", s, e))
+}
+
+/// Deserializes a decimal float given either as JSON number or as string such as `"0.0123"`.
+pub fn from_decimal_str<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where D: Deserializer<'de>
+{
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(v) => Ok(v),
+        NumberOrString::String(s) => s.trim().parse().map_err(de::Error::custom),
+    }
+}
+
+/// Like `from_decimal_str` but treats absent and blank values as `None`.
+pub fn from_optional_decimal_str<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where D: Deserializer<'de>
+{
+    match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(NumberOrString::Number(v)) => Ok(Some(v)),
+        Some(NumberOrString::String(s)) if !s.trim().is_empty() => s.trim().parse().map(Some).map_err(de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(f64),
+    String(String),
+}
+
+/// Like `from_str` but drops thousands separators, as in `"2,408,028.7423"`.
+pub fn from_grouped_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: FromStr,
+          T::Err: Display,
+          D: Deserializer<'de>
+{
+    let s = String::deserialize(deserializer)?;
+    T::from_str(&s.replace(',', "")).map_err(de::Error::custom)
+}
+
+/// Deserializes a unix timestamp in seconds such as `"1589890583"` into a UTC datetime.
+pub fn from_unix_timestamp_str<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where D: Deserializer<'de>
+{
+    let secs: i64 = from_str(deserializer)?;
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| de::Error::custom(format!("invalid unix timestamp {}", secs)))
+}
+
+/// Deserializes a decimal gwei string such as `"12.345678912"` into wei without going through a float.
+pub fn from_gwei_str<'de, D>(deserializer: D) -> Result<u128, D::Error>
+    where D: Deserializer<'de>
+{
+    let s = String::deserialize(deserializer)?;
+    parse_gwei(&s).map_err(de::Error::custom)
+}
+
+/// Deserializes a comma separated list such as `"0.5,0.91,0.23"`.
+pub fn from_str_list<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where T: FromStr,
+          T::Err: Display,
+          D: Deserializer<'de>
+{
+    let s = String::deserialize(deserializer)?;
+    if s.is_empty() {
+        return Ok(vec![]);
+    }
+    s.split(',')
+        .map(|item| T::from_str(item.trim()).map_err(de::Error::custom))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Record {
+        #[serde(default, deserialize_with = "from_optional_str")]
+        to: Option<String>,
+        #[serde(default, deserialize_with = "from_optional_hex_or_dec_str")]
+        gas_used: Option<u64>,
+        #[serde(default, deserialize_with = "from_optional_decimal_str")]
+        ratio: Option<f64>,
+    }
+
+    #[test]
+    fn blank_fields_become_none() {
+        let record: Record = serde_json::from_str(r#"{"to": "", "gas_used": "", "ratio": ""}"#).unwrap();
+        assert_eq!(record.to, None);
+        assert_eq!(record.gas_used, None);
+        assert_eq!(record.ratio, None);
+
+        let record: Record = serde_json::from_str("{}").unwrap();
+        assert_eq!(record.to, None);
+        assert_eq!(record.gas_used, None);
+
+        let record: Record = serde_json::from_str(r#"{"to": "0xabc", "gas_used": "0x5208", "ratio": 0.5}"#).unwrap();
+        assert_eq!(record.to.as_deref(), Some("0xabc"));
+        assert_eq!(record.gas_used, Some(21000));
+        assert_eq!(record.ratio, Some(0.5));
+    }
+
+    #[test]
+    fn hex_or_dec() {
+        assert_eq!(parse_hex_or_dec("0x5208"), Ok(21000));
+        assert_eq!(parse_hex_or_dec("21000"), Ok(21000));
+        assert_eq!(parse_hex_or_dec("0x"), Ok(0));
+        assert!(parse_hex_or_dec("0xzz").is_err());
+        assert!(parse_hex_or_dec("").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Spreading requests over several API keys.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::time::Instant;
+
+use super::rate_limit::RateLimiter;
+
+/// Rate limit errors in a row after which a key is quarantined.
+const MAX_RATE_LIMIT_STRIKES: u32 = 3;
+
+#[derive(Debug)]
+pub struct NoKeyAvailableError;
+
+impl fmt::Display for NoKeyAvailableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "all API keys are quarantined")
+    }
+}
+
+impl std::error::Error for NoKeyAvailableError {}
+
+/// What a response tells about the key it was requested with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum KeyOutcome {
+    Ok,
+    InvalidKey,
+    RateLimited,
+}
+
+impl KeyOutcome {
+    pub(crate) fn of(body: &str) -> KeyOutcome {
+        let value: Value = match serde_json::from_str(body) {
+            Ok(value) => value,
+            Err(_) => return KeyOutcome::Ok,
+        };
+        if value["status"] != "0" {
+            return KeyOutcome::Ok;
+        }
+        let result = value["result"].as_str().unwrap_or("").to_lowercase();
+        if result.contains("invalid api key") {
+            KeyOutcome::InvalidKey
+        } else if result.contains("rate limit") {
+            KeyOutcome::RateLimited
+        } else {
+            KeyOutcome::Ok
+        }
+    }
+}
+
+struct Key {
+    key: String,
+    rate_limiter: RateLimiter,
+    quarantined_until: Mutex<Option<Instant>>,
+    rate_limit_strikes: AtomicU32,
+}
+
+impl Key {
+    fn is_available(&self, now: Instant) -> bool {
+        match *self.quarantined_until.lock().unwrap() {
+            Some(until) => until <= now,
+            None => true,
+        }
+    }
+}
+
+/// A set of API keys used in turn, each within its own rate budget. A key which is reported
+/// invalid, or rate limited several times in a row, is left out for the quarantine period.
+pub struct KeyPool {
+    keys: Vec<Key>,
+    next: AtomicUsize,
+    quarantine: Duration,
+}
+
+impl KeyPool {
+    /// `requests_per_second` is the budget of each key.
+    pub fn new(keys: &[&str], requests_per_second: u32) -> KeyPool {
+        KeyPool {
+            keys: keys.iter().map(|key| Key {
+                key: key.to_string(),
+                rate_limiter: RateLimiter::new(requests_per_second),
+                quarantined_until: Mutex::new(None),
+                rate_limit_strikes: AtomicU32::new(0),
+            }).collect(),
+            next: AtomicUsize::new(0),
+            quarantine: Duration::from_secs(600),
+        }
+    }
+
+    /// 10 minutes by default.
+    pub fn with_quarantine(mut self, quarantine: Duration) -> KeyPool {
+        self.quarantine = quarantine;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The time between two requests if all keys are available.
+    pub fn interval(&self) -> Duration {
+        match self.keys.first() {
+            Some(key) => key.rate_limiter.interval() / self.keys.len() as u32,
+            None => Duration::default(),
+        }
+    }
+
+    /// Picks the next available key and waits for its rate budget. Returns the key's index.
+    pub(crate) async fn acquire(&self) -> Result<usize, NoKeyAvailableError> {
+        let now = Instant::now();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let index = (0..self.keys.len())
+            .map(|i| (start + i) % self.keys.len())
+            .find(|i| self.keys[*i].is_available(now))
+            .ok_or(NoKeyAvailableError)?;
+        self.keys[index].rate_limiter.acquire().await;
+        Ok(index)
+    }
+
+    pub(crate) fn key(&self, index: usize) -> &str {
+        &self.keys[index].key
+    }
+
+    pub(crate) fn report(&self, index: usize, outcome: KeyOutcome) {
+        let key = &self.keys[index];
+        let quarantine = match outcome {
+            KeyOutcome::Ok => {
+                key.rate_limit_strikes.store(0, Ordering::Relaxed);
+                false
+            }
+            KeyOutcome::InvalidKey => true,
+            KeyOutcome::RateLimited => key.rate_limit_strikes.fetch_add(1, Ordering::Relaxed) + 1 >= MAX_RATE_LIMIT_STRIKES,
+        };
+        if quarantine {
+            key.rate_limit_strikes.store(0, Ordering::Relaxed);
+            *key.quarantined_until.lock().unwrap() = Some(Instant::now() + self.quarantine);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rotates_and_quarantines() {
+        let pool = KeyPool::new(&["a", "b", "c"], 100);
+        let mut used = vec![];
+        for _ in 0..3 {
+            used.push(pool.key(pool.acquire().await.unwrap()).to_string());
+        }
+        assert_eq!(used, ["a", "b", "c"]);
+
+        pool.report(1, KeyOutcome::InvalidKey);
+        for _ in 0..MAX_RATE_LIMIT_STRIKES {
+            pool.report(2, KeyOutcome::RateLimited);
+        }
+        for _ in 0..3 {
+            assert_eq!(pool.key(pool.acquire().await.unwrap()), "a");
+        }
+        pool.report(0, KeyOutcome::InvalidKey);
+        assert!(pool.acquire().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn quarantine_ends() {
+        let pool = KeyPool::new(&["a"], 100).with_quarantine(Duration::from_millis(0));
+        pool.report(0, KeyOutcome::InvalidKey);
+        assert!(pool.acquire().await.is_ok());
+    }
+
+    #[test]
+    fn outcome_of_response() {
+        assert_eq!(KeyOutcome::of(r#"{"status":"0","message":"NOTOK","result":"Invalid API Key"}"#), KeyOutcome::InvalidKey);
+        assert_eq!(KeyOutcome::of(r#"{"status":"0","message":"NOTOK","result":"Max rate limit reached"}"#), KeyOutcome::RateLimited);
+        assert_eq!(KeyOutcome::of(r#"{"status":"0","message":"No transactions found","result":[]}"#), KeyOutcome::Ok);
+        assert_eq!(KeyOutcome::of(r#"{"jsonrpc":"2.0","id":1,"result":"0x1"}"#), KeyOutcome::Ok);
+    }
+}
@@ -1,22 +1,65 @@
+use std::collections::{BTreeMap, HashMap};
 use std::env::VarError;
 use std::fmt;
 use std::fmt::Debug;
+use std::ops::{Bound, RangeBounds};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use serde::export::Formatter;
+use tokio::time::{delay_for, Instant};
 
+use abi::{Abi, DecodedCall, FunctionIndex};
+use activity::ActivityEntry;
+use cache::{Cache, CachePolicy};
+use endpoint::Call;
+use keys::{KeyOutcome, KeyPool};
+use metrics::Metrics;
+use rate_limit::RateLimiter;
+use request::Request;
 use format::*;
 use models::*;
 
-mod models;
-mod format;
+pub use endpoint::Endpoint;
+pub use primitive_types::U256;
+
+pub mod abi;
+pub mod activity;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod endpoint;
+pub mod events;
+pub mod export;
+pub mod keys;
+pub mod metrics;
+pub mod models;
+pub mod rate_limit;
+mod request;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod state;
+pub mod sync;
+pub mod units;
+pub mod watcher;
+pub mod format;
 
 type AsyncError = Box<dyn std::error::Error + Send + Sync>;
 
 const BASE_URL: &str = "https://api.etherscan.io/api";
 const ETHERSCANIO_API_TOKEN: &str = "ETHERSCANIO_API_TOKEN";
+/// Etherscan's own default `endblock`, used for ranges open at the end.
+pub(crate) const LATEST_BLOCK: u64 = 99_999_999;
+const ACTIVITY_PAGE_SIZE: u64 = 1000;
+const TX_POLL_INTERVAL: Duration = Duration::from_secs(5);
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Response<T>
@@ -32,6 +75,8 @@ struct Response<T>
 impl<T: 'static> Response<T> where T: Debug + Send + Sync {
     fn result_or_error(self) -> Result<T, AsyncError> {
         match self.status {
+            // empty lists come with status 0, e.g. "No transactions found"
+            StatusCode::Error if self.message.starts_with("No ") && self.message.ends_with(" found") => Ok(self.result),
             StatusCode::Error => {
                 Err(Box::new(ResponseError { status_code: self.status, message: self.message, result: self.result }))
             }
@@ -72,13 +117,13 @@ impl fmt::Display for StatusCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             StatusCode::Ok => {
-                write!(f, "{}", "ok")
+                write!(f, "ok")
             }
             StatusCode::Error => {
-                write!(f, "{}", "error")
+                write!(f, "error")
             }
             StatusCode::Unknown => {
-                write!(f, "{}", "unknown")
+                write!(f, "unknown")
             }
         }
     }
@@ -96,80 +141,484 @@ impl FromStr for StatusCode {
     }
 }
 
+#[derive(Debug)]
+struct PriceNotAvailableError {
+    date: NaiveDate,
+}
+
+impl fmt::Display for PriceNotAvailableError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "no daily ETH price available for {}", self.date)
+    }
+}
+
+impl std::error::Error for PriceNotAvailableError {}
+
+/// An error reported by the proxy module in JSON-RPC format.
+#[derive(Deserialize, Debug)]
+struct ProxyError {
+    code: i64,
+    message: String,
+}
+
+impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "proxy error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ProxyError {}
+
+#[derive(Deserialize, Debug)]
+struct ProxyResponse {
+    result: Option<serde_json::Value>,
+    error: Option<ProxyError>,
+}
+
+/// A failed HTTP request, with the API key masked in the URL.
+#[derive(Debug)]
+struct RequestError {
+    message: String,
+}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(error: reqwest::Error) -> RequestError {
+        RequestError { message: redact_api_key(&error.to_string()) }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+#[derive(Debug)]
+struct WaitTimeoutError {
+    tx_hash: String,
+}
+
+impl fmt::Display for WaitTimeoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "transaction {} is still pending or not confirmed enough", self.tx_hash)
+    }
+}
+
+impl std::error::Error for WaitTimeoutError {}
+
+/// The client of the Etherscan API.
+///
+/// Etherscan takes the API key as query parameter. Errors redact it, but reqwest logs the URL of
+/// every response, key included, at debug level under the `reqwest::async_impl::client` target.
+/// Keep the `reqwest` target at info or above wherever logs leave the process.
 pub struct API {
     api_token: String,
+    base_url: String,
     client: Client,
+    // daily ETH/USD closing prices fetched through price_at
+    daily_prices: Mutex<BTreeMap<NaiveDate, f64>>,
+    // the last day fetched of each year, days up to it without a price have none
+    daily_prices_fetched: Mutex<HashMap<i32, NaiveDate>>,
+    // contract ABIs fetched through decode_input by lowercase address, or why a contract has none
+    abis: Mutex<HashMap<String, Result<Arc<FunctionIndex>, String>>>,
+    cache: Option<Arc<dyn Cache>>,
+    cache_policy: CachePolicy,
+    // the latest block number seen in any response, used to tell final block ranges apart
+    head_block: AtomicU64,
+    rate_limiter: Option<RateLimiter>,
+    key_pool: Option<KeyPool>,
+    // the chain requests are labelled with in spans and metrics
+    chain: Chain,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+/// What happened while answering a call, for spans and metrics.
+#[derive(Default)]
+struct RequestStats {
+    retries: u32,
+    cache_hit: bool,
+}
+
+impl Debug for API {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("API")
+            .field("api_token", &"***")
+            .field("base_url", &self.base_url)
+            .field("cache_policy", &self.cache_policy)
+            .finish()
+    }
 }
 
 impl API {
     pub fn new(api_token: &str) -> API {
-        API { api_token: api_token.into(), client: reqwest::Client::new() }
+        API {
+            api_token: api_token.into(),
+            base_url: BASE_URL.into(),
+            client: reqwest::Client::new(),
+            daily_prices: Mutex::new(BTreeMap::new()),
+            daily_prices_fetched: Mutex::new(HashMap::new()),
+            abis: Mutex::new(HashMap::new()),
+            cache: None,
+            cache_policy: CachePolicy::default(),
+            rate_limiter: None,
+            key_pool: None,
+            chain: Chain::Mainnet,
+            metrics: None,
+            head_block: AtomicU64::new(0),
+        }
     }
 
     pub fn new_from_env() -> Result<API, VarError> {
         let val = std::env::var(ETHERSCANIO_API_TOKEN)?;
-        Ok(API { api_token: val, client: reqwest::Client::new() })
-    }
-
-    async fn fetch_balance(&self, uri: String) -> Result<u128, AsyncError> {
-        match self.client.get(&uri).send()
-            .await?
-            .json::<Response<Balance>>()
-            .await?
-            .result_or_error() {
-            Ok(result) => match result.value() {
-                Ok(v) => Ok(v),
-                Err(e) => Err(Box::new(e)),
-            },
-            Err(e) => Err(e),
+        Ok(API::new(&val))
+    }
+
+    /// Sends requests to the given endpoint instead of https://api.etherscan.io/api,
+    /// e.g. to a `testing::FixtureServer`.
+    pub fn with_base_url(mut self, base_url: &str) -> API {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn with_chain(mut self, chain: Chain) -> API {
+        self.chain = chain;
+        self.with_base_url(chain.base_url())
+    }
+
+    /// Caches responses in the given cache according to the cache policy.
+    pub fn with_cache(mut self, cache: Arc<dyn Cache>) -> API {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn with_cache_policy(mut self, cache_policy: CachePolicy) -> API {
+        self.cache_policy = cache_policy;
+        self
+    }
+
+    /// Sends at most `requests_per_second` requests. Responses served from the cache don't count.
+    pub fn with_rate_limit(mut self, requests_per_second: u32) -> API {
+        self.rate_limiter = Some(RateLimiter::new(requests_per_second));
+        self
+    }
+
+    /// Uses the keys of the pool in turn instead of the key given to `new`. A request answered with
+    /// an invalid key or rate limit error is retried with the next key.
+    pub fn with_key_pool(mut self, key_pool: KeyPool) -> API {
+        self.key_pool = Some(key_pool);
+        self
+    }
+
+    /// Reports the metrics of every call, see the metrics module.
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> API {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// The minimum time between two requests under the rate limits, zero without any.
+    pub fn request_interval(&self) -> Duration {
+        let api_interval = self.rate_limiter.as_ref().map(RateLimiter::interval).unwrap_or_default();
+        let key_interval = self.key_pool.as_ref().map(KeyPool::interval).unwrap_or_default();
+        api_interval.max(key_interval)
+    }
+
+    async fn get(&self, request: &Request, stats: &mut RequestStats) -> Result<String, AsyncError> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let key_pool = match &self.key_pool {
+            Some(key_pool) if !key_pool.is_empty() => key_pool,
+            _ => return self.send(request, &self.api_token).await,
+        };
+        let mut body = String::new();
+        for attempt in 0..key_pool.len() {
+            if attempt > 0 {
+                stats.retries += 1;
+            }
+            let key = key_pool.acquire().await?;
+            body = self.send(request, key_pool.key(key)).await?;
+            let outcome = KeyOutcome::of(&body);
+            key_pool.report(key, outcome);
+            if outcome == KeyOutcome::Ok {
+                break;
+            }
         }
+        // the last key's error ends up as the usual response error
+        Ok(body)
+    }
+
+    /// Sends the request with the API key added. Errors redact the key, reqwest's debug log
+    /// doesn't, see `API`.
+    async fn send(&self, request: &Request, api_key: &str) -> Result<String, AsyncError> {
+        let response = self.client.get(&self.base_url).query(&request.query()).query(&[("apikey", api_key)]).send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(RequestError::from)?;
+        Ok(response.text().await.map_err(RequestError::from)?)
+    }
+
+    /// Calls the endpoint, which may be an action the crate has no method for.
+    pub async fn call<E: Endpoint>(&self, endpoint: E) -> Result<E::Response, AsyncError> {
+        let request = endpoint.params().iter()
+            .fold(Request::new(endpoint.module(), endpoint.action()), |request, (name, value)| request.param(name, value));
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("etherscan_request", module = request.module(), action = request.action(), chain = %self.chain,
+                                       status = tracing::field::Empty, latency_ms = tracing::field::Empty,
+                                       retries = tracing::field::Empty, cache_hit = tracing::field::Empty);
+        let start = Instant::now();
+        let mut stats = RequestStats::default();
+        let fetching = async {
+            match request.module() {
+                "proxy" => self.fetch_proxy(&request, &mut stats).await,
+                _ => self.fetch(&request, &mut stats).await,
+            }
+        };
+        #[cfg(feature = "tracing")]
+        let fetching = tracing::Instrument::instrument(fetching, span.clone());
+        let result = fetching.await;
+        let latency = start.elapsed();
+        let status = if result.is_ok() { "ok" } else { "error" };
+        #[cfg(feature = "tracing")]
+        {
+            span.record("status", status);
+            span.record("latency_ms", latency.as_millis() as u64);
+            span.record("retries", stats.retries);
+            span.record("cache_hit", stats.cache_hit);
+        }
+        if let Some(metrics) = &self.metrics {
+            let chain = self.chain.to_string();
+            let labels = [("module", request.module()), ("action", request.action()), ("chain", chain.as_str())];
+            metrics.increment_counter(metrics::REQUESTS, &[labels[0], labels[1], labels[2], ("status", status)], 1);
+            metrics.record_histogram(metrics::REQUEST_DURATION, &labels, latency.as_secs_f64());
+            if stats.retries > 0 {
+                metrics.increment_counter(metrics::RETRIES, &labels, stats.retries as u64);
+            }
+            if stats.cache_hit {
+                metrics.increment_counter(metrics::CACHE_HITS, &labels, 1);
+            }
+        }
+        result
+    }
+
+    /// Calls an action by name, returning the `result` of the response as is.
+    pub async fn raw(&self, module: &str, action: &str, params: &[(&str, &str)]) -> Result<serde_json::Value, AsyncError> {
+        let request = params.iter().fold(Request::new(module, action), |request, (name, value)| request.param(name, value));
+        self.call(Call::<serde_json::Value>::new(request)).await
+    }
+
+    async fn fetch<T>(&self, request: &Request, stats: &mut RequestStats) -> Result<T, AsyncError>
+        where T: DeserializeOwned + Debug + Send + Sync + 'static
+    {
+        let uri = request.url(&self.base_url)?;
+        let key = cache::cache_key(&uri);
+        if let Some(cache) = &self.cache {
+            if let Some(body) = cache.get(&key) {
+                match serde_json::from_str::<Response<T>>(&body) {
+                    Ok(response) => {
+                        stats.cache_hit = true;
+                        return response.result_or_error();
+                    }
+                    // e.g. an entry cut short by a crash, which is refetched
+                    Err(_) => cache.remove(&key),
+                }
+            }
+        }
+
+        let body = self.get(request, stats).await?;
+        let response = serde_json::from_str::<Response<T>>(&body)?;
+        if let (Some(cache), StatusCode::Ok) = (&self.cache, &response.status) {
+            let seen_head_block = cache::head_block(&body);
+            let head_block = self.head_block.fetch_max(seen_head_block, Ordering::Relaxed).max(seen_head_block);
+            if let Some(ttl) = self.cache_policy.ttl(&uri, &body, head_block) {
+                cache.set(&key, &body, ttl);
+            }
+        }
+        response.result_or_error()
+    }
+
+    /// Fetches from the proxy module, which answers in JSON-RPC format. Proxy responses are never cached.
+    async fn fetch_proxy<T>(&self, request: &Request, stats: &mut RequestStats) -> Result<T, AsyncError>
+        where T: DeserializeOwned
+    {
+        let body = self.get(request, stats).await?;
+        let value: serde_json::Value = serde_json::from_str(&body)?;
+        if value.get("jsonrpc").is_none() {
+            // invalid keys and rate limits are reported in the format of the other modules
+            let result = serde_json::from_value::<Response<serde_json::Value>>(value)?.result_or_error()?;
+            return Ok(serde_json::from_value(result)?);
+        }
+        let response: ProxyResponse = serde_json::from_value(value)?;
+        match response.error {
+            Some(error) => Err(Box::new(error)),
+            None => Ok(serde_json::from_value(response.result.unwrap_or_default())?),
+        }
+    }
+
+    async fn fetch_balance(&self, request: Request) -> Result<u128, AsyncError> {
+        Ok(self.call(Call::<Balance>::new(request)).await?.value()?)
     }
 
     pub async fn acc_balance(&self, account_addr: &str) -> Result<u128, AsyncError> {
-        let uri = format!("{}/?module=account&action=balance&address={}&tag=latest&apikey={}", BASE_URL, account_addr, self.api_token);
-        self.fetch_balance(uri).await
+        let request = Request::new("account", "balance").param("address", account_addr).param("tag", "latest");
+        self.fetch_balance(request).await
     }
 
     pub async fn estimate_conf_time_for_gas(&self, gas: u128) -> Result<u128, AsyncError> {
-        let uri = format!("{}?module=gastracker&action=gasestimate&gasprice={}&apikey={}", BASE_URL, gas, self.api_token);
-        self.fetch_balance(uri).await
+        let request = Request::new("gastracker", "gasestimate").param("gasprice", gas);
+        self.fetch_balance(request).await
     }
 
     pub async fn gas_oracle(&self) -> Result<GasOracle, AsyncError> {
-        let uri = format!("{}?module=gastracker&action=gasoracle&apikey={}", BASE_URL, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<GasOracle>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("gastracker", "gasoracle");
+        self.call(Call::<GasOracle>::new(request)).await
+    }
+
+    async fn fetch_daily_stats<T>(&self, action: &str, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<T>>, AsyncError>
+        where T: DeserializeOwned + Debug + Send + Sync + 'static
+    {
+        let request = Request::new("stats", action).date_range(start_date, end_date).param("sort", "asc");
+        self.call(Call::<Vec<DailyStat<T>>>::new(request)).await
+    }
+
+    pub async fn daily_avg_gas_limit(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyGasLimit>>, AsyncError> {
+        self.fetch_daily_stats("dailyavggaslimit", start_date, end_date).await
+    }
+
+    pub async fn daily_gas_used(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyGasUsed>>, AsyncError> {
+        self.fetch_daily_stats("dailygasused", start_date, end_date).await
+    }
+
+    pub async fn daily_avg_gas_price(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyGasPrice>>, AsyncError> {
+        self.fetch_daily_stats("dailyavggasprice", start_date, end_date).await
     }
 
     pub async fn eth_price(&self) -> Result<ETHPrice, AsyncError> {
-        let uri = format!("{}?module=stats&action=ethprice&apikey={}", BASE_URL, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<ETHPrice>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("stats", "ethprice");
+        self.call(Call::<ETHPrice>::new(request)).await
+    }
+
+    pub async fn eth_daily_prices(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyPrice>>, AsyncError> {
+        self.fetch_daily_stats("ethdailyprice", start_date, end_date).await
+    }
+
+    /// Returns the ETH/USD closing price of the UTC day the given timestamp falls into.
+    /// The first lookup of a day fetches the daily prices of its year up to today, which are then
+    /// cached. Later lookups only fetch the days added since, days without a price, like today
+    /// before its price is published, aren't asked for again.
+    pub async fn price_at(&self, timestamp: DateTime<Utc>) -> Result<f64, AsyncError> {
+        let date = timestamp.naive_utc().date();
+        let today = Utc::now().naive_utc().date();
+        if date > today {
+            return Err(Box::new(PriceNotAvailableError { date }));
+        }
+        if let Some(price) = self.daily_prices.lock().unwrap().get(&date) {
+            return Ok(*price);
+        }
+
+        let fetched_until = self.daily_prices_fetched.lock().unwrap().get(&date.year()).copied();
+        if fetched_until.is_some_and(|fetched_until| date <= fetched_until) {
+            return Err(Box::new(PriceNotAvailableError { date }));
+        }
+        let start_date = match fetched_until {
+            Some(fetched_until) => fetched_until.succ_opt().unwrap(),
+            None => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap(),
+        };
+        let end_date = NaiveDate::from_ymd_opt(date.year(), 12, 31).unwrap().min(today);
+        let prices = self.eth_daily_prices(start_date, end_date).await?;
+
+        let mut cache = self.daily_prices.lock().unwrap();
+        for daily_price in prices {
+            cache.insert(daily_price.utc_date(), daily_price.value().price);
+        }
+        let mut fetched = self.daily_prices_fetched.lock().unwrap();
+        let fetched_until = fetched.entry(date.year()).or_insert(end_date);
+        *fetched_until = (*fetched_until).max(end_date);
+        match cache.get(&date) {
+            Some(price) => Ok(*price),
+            None => Err(Box::new(PriceNotAvailableError { date })),
+        }
+    }
+
+    pub async fn eth_supply(&self) -> Result<u128, AsyncError> {
+        let request = Request::new("stats", "ethsupply");
+        self.fetch_balance(request).await
+    }
+
+    pub async fn eth_supply_breakdown(&self) -> Result<ETHSupply, AsyncError> {
+        let request = Request::new("stats", "ethsupply2");
+        self.call(Call::<ETHSupply>::new(request)).await
+    }
+
+    pub async fn chain_size(&self, start_date: NaiveDate, end_date: NaiveDate, client_type: ClientType, sync_mode: SyncMode) -> Result<Vec<ChainSize>, AsyncError> {
+        let request = Request::new("stats", "chainsize").date_range(start_date, end_date)
+            .param("clienttype", client_type).param("syncmode", sync_mode).param("sort", "asc");
+        self.call(Call::<Vec<ChainSize>>::new(request)).await
+    }
+
+    pub async fn node_count(&self) -> Result<NodeCount, AsyncError> {
+        let request = Request::new("stats", "nodecount");
+        self.call(Call::<NodeCount>::new(request)).await
+    }
+
+    pub async fn daily_tx_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyTxCount>>, AsyncError> {
+        self.fetch_daily_stats("dailytx", start_date, end_date).await
+    }
+
+    pub async fn daily_new_address_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyNewAddressCount>>, AsyncError> {
+        self.fetch_daily_stats("dailynewaddress", start_date, end_date).await
+    }
+
+    pub async fn daily_network_utilization(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyNetworkUtilization>>, AsyncError> {
+        self.fetch_daily_stats("dailynetutilization", start_date, end_date).await
+    }
+
+    pub async fn daily_tx_fee(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyTxFee>>, AsyncError> {
+        self.fetch_daily_stats("dailytxnfee", start_date, end_date).await
+    }
+
+    pub async fn daily_avg_hash_rate(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyHashRate>>, AsyncError> {
+        self.fetch_daily_stats("dailyavghashrate", start_date, end_date).await
+    }
+
+    pub async fn daily_avg_difficulty(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyDifficulty>>, AsyncError> {
+        self.fetch_daily_stats("dailyavgnetdifficulty", start_date, end_date).await
+    }
+
+    pub async fn daily_avg_block_size(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyBlockSize>>, AsyncError> {
+        self.fetch_daily_stats("dailyavgblocksize", start_date, end_date).await
+    }
+
+    pub async fn daily_block_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyBlockCount>>, AsyncError> {
+        self.fetch_daily_stats("dailyblkcount", start_date, end_date).await
+    }
+
+    pub async fn daily_avg_block_time(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyBlockTime>>, AsyncError> {
+        self.fetch_daily_stats("dailyavgblocktime", start_date, end_date).await
+    }
+
+    pub async fn daily_uncle_block_count(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyUncleBlockCount>>, AsyncError> {
+        self.fetch_daily_stats("dailyuncleblkcount", start_date, end_date).await
+    }
+
+    pub async fn daily_market_cap(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<DailyMarketCap>>, AsyncError> {
+        self.fetch_daily_stats("ethdailymarketcap", start_date, end_date).await
     }
 
     pub async fn erc20_token_total_supply(&self, token_contract_addr: &str) -> Result<u128, AsyncError> {
-        let uri = format!("{}?module=stats&action=tokensupply&contractaddress={}&apikey={}", BASE_URL, token_contract_addr, self.api_token);
-        self.fetch_balance(uri).await
+        let request = Request::new("stats", "tokensupply").param("contractaddress", token_contract_addr);
+        self.fetch_balance(request).await
     }
 
     pub async fn erc20_token_balance_on_account(&self, account_addr: &str, token_contract_addr: &str) -> Result<u128, AsyncError> {
-        let uri = format!("{}?module=account&action=tokenbalance&contractaddress={}&address={}&tag=latest&apikey={}", BASE_URL, token_contract_addr, account_addr, self.api_token);
-        self.fetch_balance(uri).await
+        let request = Request::new("account", "tokenbalance").param("contractaddress", token_contract_addr).param("address", account_addr).param("tag", "latest");
+        self.fetch_balance(request).await
     }
 
     pub async fn txs_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<Transaction>, AsyncError> {
-        let uri = format!("{}?module=account&action=txlist&address={}{}&sort=asc&apikey={}", BASE_URL, account_addr, parse_block_range(from_block, end_block), self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<Transaction>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "txlist").param("address", account_addr).block_range(from_block, end_block).param("sort", "asc");
+        self.call(Call::<Vec<Transaction>>::new(request)).await
     }
 
     pub async fn txs_on_account(&self, account_addr: &str) -> Result<Vec<Transaction>, AsyncError> {
@@ -177,12 +626,8 @@ impl API {
     }
 
     pub async fn internal_txs_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<InternalTransaction>, AsyncError> {
-        let uri = format!("{}?module=account&action=txlistinternal&address={}{}&sort=asc&apikey={}", BASE_URL, account_addr, parse_block_range(from_block, end_block), self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<InternalTransaction>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "txlistinternal").param("address", account_addr).block_range(from_block, end_block).param("sort", "asc");
+        self.call(Call::<Vec<InternalTransaction>>::new(request)).await
     }
 
     pub async fn internal_txs_on_account(&self, addr: &str) -> Result<Vec<InternalTransaction>, AsyncError> {
@@ -190,30 +635,18 @@ impl API {
     }
 
     pub async fn internal_txs_from_to(&self, from_block: u64, end_block: u64) -> Result<Vec<InternalTransaction>, AsyncError> {
-        let uri = format!("{}?module=account&action=txlistinternal{}&page=1&offset=10&sort=asc&apikey={}", BASE_URL, parse_block_range(from_block, end_block), self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<InternalTransaction>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "txlistinternal").block_range(from_block, end_block).param("page", 1).param("offset", 10).param("sort", "asc");
+        self.call(Call::<Vec<InternalTransaction>>::new(request)).await
     }
 
     pub async fn internal_txs_by_tx_hash(&self, tx_hash: &str) -> Result<Vec<InternalTransaction>, AsyncError> {
-        let uri = format!("{}?module=account&action=txlistinternal&txhash={}&apikey={}", BASE_URL, tx_hash, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<InternalTransaction>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "txlistinternal").param("txhash", tx_hash);
+        self.call(Call::<Vec<InternalTransaction>>::new(request)).await
     }
 
     pub async fn erc20_transfers_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<ERC20TokenTransferEvent>, AsyncError> {
-        let uri = format!("{}?module=account&action=tokentx&address={}{}&sort=asc&apikey={}", BASE_URL, account_addr, parse_block_range(from_block, end_block), self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<ERC20TokenTransferEvent>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "tokentx").param("address", account_addr).block_range(from_block, end_block).param("sort", "asc");
+        self.call(Call::<Vec<ERC20TokenTransferEvent>>::new(request)).await
     }
 
     pub async fn erc20_transfer_events_on_account(&self, account_addr: &str) -> Result<Vec<ERC20TokenTransferEvent>, AsyncError> {
@@ -221,21 +654,13 @@ impl API {
     }
 
     pub async fn erc20_transfers_on_account_by_contract(&self, account_addr: &str, token_contract_addr: &str) -> Result<Vec<ERC20TokenTransferEvent>, AsyncError> {
-        let uri = format!("{}?module=account&action=tokentx&contractaddress={}&address={}&sort=asc&apikey={}", BASE_URL, token_contract_addr, account_addr, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<ERC20TokenTransferEvent>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "tokentx").param("contractaddress", token_contract_addr).param("address", account_addr).param("sort", "asc");
+        self.call(Call::<Vec<ERC20TokenTransferEvent>>::new(request)).await
     }
 
     pub async fn erc271_transfers_on_account_from_to(&self, account_addr: &str, from_block: u64, end_block: u64) -> Result<Vec<ERC721TokenTransferEvent>, AsyncError> {
-        let uri = format!("{}?module=account&action=tokennfttx&address={}{}&sort=asc&apikey={}", BASE_URL, account_addr, parse_block_range(from_block, end_block), self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<ERC721TokenTransferEvent>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "tokennfttx").param("address", account_addr).block_range(from_block, end_block).param("sort", "asc");
+        self.call(Call::<Vec<ERC721TokenTransferEvent>>::new(request)).await
     }
 
     pub async fn erc271_transfers_on_account(&self, account_addr: &str) -> Result<Vec<ERC721TokenTransferEvent>, AsyncError> {
@@ -243,207 +668,598 @@ impl API {
     }
 
     pub async fn erc271_transfers_on_account_by_contract(&self, account_addr: &str, token_contract_addr: &str) -> Result<Vec<ERC721TokenTransferEvent>, AsyncError> {
-        let uri = format!("{}?module=account&action=tokennfttx&contractaddress={}&address={}&sort=asc&apikey={}", BASE_URL, token_contract_addr, account_addr, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<ERC721TokenTransferEvent>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "tokennfttx").param("contractaddress", token_contract_addr).param("address", account_addr).param("sort", "asc");
+        self.call(Call::<Vec<ERC721TokenTransferEvent>>::new(request)).await
+    }
+
+    /// Fetches the account's normal and internal transactions and its ERC20 and ERC721 transfers
+    /// within the block range concurrently and joins them by tx hash into a timeline. The lists are
+    /// paged through, so they aren't cut off at Etherscan's 10000 records per query.
+    pub async fn account_activity<R: RangeBounds<u64>>(&self, account_addr: &str, blocks: R) -> Result<Vec<ActivityEntry>, AsyncError> {
+        let from_block = match blocks.start_bound() {
+            Bound::Included(b) => Some(*b),
+            Bound::Excluded(b) => b.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end_block = match blocks.end_bound() {
+            Bound::Included(b) => Some(*b),
+            Bound::Excluded(b) => b.checked_sub(1),
+            Bound::Unbounded => Some(LATEST_BLOCK),
+        };
+        let (from_block, end_block) = match (from_block, end_block) {
+            (Some(from_block), Some(end_block)) if from_block <= end_block => (from_block, end_block),
+            _ => return Ok(vec![]),
+        };
+        let (txs, internal_txs, erc20_transfers, erc721_transfers) = tokio::try_join!(
+            state::fetch_range(self, account_addr, from_block, end_block, ACTIVITY_PAGE_SIZE),
+            state::fetch_range(self, account_addr, from_block, end_block, ACTIVITY_PAGE_SIZE),
+            state::fetch_range(self, account_addr, from_block, end_block, ACTIVITY_PAGE_SIZE),
+            state::fetch_range(self, account_addr, from_block, end_block, ACTIVITY_PAGE_SIZE),
+        )?;
+        Ok(activity::timeline(txs, internal_txs, erc20_transfers, erc721_transfers))
+    }
+
+    /// A page of an account's list, e.g. of `txlist`, sorted ascending.
+    async fn fetch_account_page<R>(&self, action: &str, account_addr: &str, from_block: u64, end_block: u64, page: u64, offset: u64) -> Result<Vec<R>, AsyncError>
+        where R: DeserializeOwned + Debug + Send + Sync + 'static
+    {
+        let request = Request::new("account", action).param("address", account_addr)
+            .param("startblock", from_block).param("endblock", end_block).param("page", page).param("offset", offset).param("sort", "asc");
+        self.call(Call::<Vec<R>>::new(request)).await
+    }
+
+    pub async fn contract_abi(&self, contract_addr: &str) -> Result<Abi, AsyncError> {
+        let request = Request::new("contract", "getabi").param("address", contract_addr);
+        let abi = self.call(Call::<String>::new(request)).await?;
+        Ok(Abi::from_json(&abi)?)
+    }
+
+    /// Decodes the input of a call to the given contract into the called function and its arguments.
+    /// Common ERC20/ERC721/ERC1155 functions are decoded right away, otherwise the contract's ABI is fetched
+    /// once and cached for subsequent calls.
+    pub async fn decode_input(&self, contract_addr: &str, input: &str) -> Result<DecodedCall, AsyncError> {
+        if let Ok(call) = FunctionIndex::known().decode_input(input) {
+            return Ok(call);
+        }
+        let key = contract_addr.to_lowercase();
+        let cached = self.abis.lock().unwrap().get(&key).cloned();
+        let abi = match cached {
+            Some(Ok(abi)) => abi,
+            Some(Err(message)) => return Err(message.into()),
+            None => match self.contract_abi(contract_addr).await {
+                Ok(abi) => {
+                    let abi = Arc::new(FunctionIndex::new(&abi));
+                    self.abis.lock().unwrap().insert(key, Ok(abi.clone()));
+                    abi
+                }
+                // an unverified contract stays unverified for a while, other errors may pass
+                Err(e) if e.to_string().contains("not verified") => {
+                    self.abis.lock().unwrap().insert(key, Err(e.to_string()));
+                    return Err(e);
+                }
+                Err(e) => return Err(e),
+            },
+        };
+        Ok(abi.decode_input(input)?)
+    }
+
+    /// Fetches the logs matching the filter, at most 1000 per call.
+    pub async fn logs(&self, filter: &LogFilter) -> Result<Vec<Log>, AsyncError> {
+        let request = filter.to_request();
+        self.call(Call::<Vec<Log>>::new(request)).await
     }
 
     pub async fn mined_blocks_by_account(&self, account_addr: &str) -> Result<Vec<MinedBlock>, AsyncError> {
-        let uri = format!("{}?module=account&action=getminedblocks&address={}&blocktype=blocks&apikey={}", BASE_URL, account_addr, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<Vec<MinedBlock>>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("account", "getminedblocks").param("address", account_addr).param("blocktype", "blocks");
+        self.call(Call::<Vec<MinedBlock>>::new(request)).await
     }
 
     pub async fn contract_execution_status(&self, tx_hash: &str) -> Result<ContractExecutionStatus, AsyncError> {
-        let uri = format!("{}?module=transaction&action=getstatus&txhash={}&apikey={}", BASE_URL, tx_hash, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<ContractExecutionStatus>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("transaction", "getstatus").param("txhash", tx_hash);
+        self.call(Call::<ContractExecutionStatus>::new(request)).await
     }
 
     pub async fn tx_receipt_status(&self, tx_hash: &str) -> Result<TransactionReceiptStatus, AsyncError> {
-        let uri = format!("{}?module=transaction&action=gettxreceiptstatus&txhash={}&apikey={}", BASE_URL, tx_hash, self.api_token);
-        self.client.get(&uri).send()
-            .await?
-            .json::<Response<TransactionReceiptStatus>>()
-            .await?
-            .result_or_error()
+        let request = Request::new("transaction", "gettxreceiptstatus").param("txhash", tx_hash);
+        self.call(Call::<TransactionReceiptStatus>::new(request)).await
+    }
+
+    pub async fn block_number(&self) -> Result<u64, AsyncError> {
+        let request = Request::new("proxy", "eth_blockNumber");
+        let number = self.call(Call::<String>::new(request)).await?;
+        Ok(parse_hex_or_dec(&number)? as u64)
+    }
+
+    /// The number of transactions the account sent, which is the nonce of its next transaction.
+    pub async fn tx_count(&self, account_addr: &str) -> Result<u64, AsyncError> {
+        let request = Request::new("proxy", "eth_getTransactionCount").param("address", account_addr).param("tag", "latest");
+        let count = self.call(Call::<String>::new(request)).await?;
+        Ok(parse_hex_or_dec(&count)? as u64)
+    }
+
+    /// `None` if the transaction is unknown.
+    pub async fn tx_by_hash(&self, tx_hash: &str) -> Result<Option<ProxyTransaction>, AsyncError> {
+        let request = Request::new("proxy", "eth_getTransactionByHash").param("txhash", tx_hash);
+        self.call(Call::<Option<ProxyTransaction>>::new(request)).await
+    }
+
+    /// `None` if the transaction is pending or unknown.
+    pub async fn tx_receipt(&self, tx_hash: &str) -> Result<Option<TransactionReceipt>, AsyncError> {
+        let request = Request::new("proxy", "eth_getTransactionReceipt").param("txhash", tx_hash);
+        self.call(Call::<Option<TransactionReceipt>>::new(request)).await
+    }
+
+    /// Polls until the transaction is mined with the given number of confirmations, or until its
+    /// sender's nonce is used by another transaction. Fails if the transaction is still pending or
+    /// lacks confirmations after `timeout`; a transaction which is unknown by then counts as dropped.
+    pub async fn wait_for_tx(&self, tx_hash: &str, confirmations: u64, timeout: Duration) -> Result<TxOutcome, AsyncError> {
+        let deadline = Instant::now() + timeout;
+        // sender and nonce, known once the transaction has been seen
+        let mut sender: Option<(String, u64)> = None;
+        loop {
+            let known = match self.tx_receipt(tx_hash).await? {
+                Some(receipt) => {
+                    let head_block = self.block_number().await?;
+                    if head_block + 1 >= receipt.block_number() + confirmations {
+                        let status = match receipt.status() {
+                            Some(ReceiptStatus::Pass) => ReceiptStatus::Pass,
+                            // the revert reason, and the status of pre-Byzantium transactions, come from the status endpoint
+                            _ => self.contract_execution_status(tx_hash).await?.status(),
+                        };
+                        return Ok(TxOutcome::Mined { receipt, status });
+                    }
+                    true
+                }
+                None => match self.tx_by_hash(tx_hash).await? {
+                    Some(tx) => {
+                        sender = Some((tx.from().into(), tx.nonce()));
+                        true
+                    }
+                    None => {
+                        if let Some((from, nonce)) = &sender {
+                            if nonce_used(self.tx_count(from).await?, *nonce) {
+                                return Ok(TxOutcome::Replaced { from: from.clone(), nonce: *nonce });
+                            }
+                        }
+                        false
+                    }
+                },
+            };
+            let now = Instant::now();
+            if now >= deadline {
+                if known {
+                    return Err(Box::new(WaitTimeoutError { tx_hash: tx_hash.into() }));
+                }
+                return Ok(TxOutcome::Dropped);
+            }
+            delay_for(TX_POLL_INTERVAL.min(deadline - now)).await;
+        }
     }
 }
 
-fn parse_block_range(from: u64, to: u64) -> String {
-    if to == 0 {
-        return "".to_string();
+/// Whether an account's transaction count shows the nonce as used, by whichever transaction.
+fn nonce_used(tx_count: u64, nonce: u64) -> bool {
+    tx_count > nonce
+}
+
+/// Masks the values of `apikey` parameters in URLs and messages containing them.
+pub(crate) fn redact_api_key(s: &str) -> String {
+    let mut redacted = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find("apikey=") {
+        let (head, tail) = rest.split_at(i + "apikey=".len());
+        redacted.push_str(head);
+        redacted.push_str("***");
+        let end = tail.find(|c: char| c == '&' || c == ')' || c == '"' || c == '#' || c.is_whitespace()).unwrap_or(tail.len());
+        rest = &tail[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Selects logs by block range, emitting contract and topics. Topics which are set are combined with `and`.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    pub address: Option<String>,
+    pub topics: [Option<String>; 4],
+}
+
+impl LogFilter {
+    fn to_request(&self) -> Request {
+        let mut request = Request::new("logs", "getLogs")
+            .optional_param("fromBlock", self.from_block)
+            .optional_param("toBlock", self.to_block)
+            .optional_param("address", self.address.as_ref());
+        let mut set_topics = vec![];
+        for (i, topic) in self.topics.iter().enumerate() {
+            if let Some(topic) = topic {
+                request = request.param(&format!("topic{}", i), topic);
+                set_topics.push(i);
+            }
+        }
+        for (i, a) in set_topics.iter().enumerate() {
+            for b in &set_topics[i + 1..] {
+                request = request.param(&format!("topic{}_{}_opr", a, b), "and");
+            }
+        }
+        request
     }
-    format!("&startblock={}&endblock={}", from, to)
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::TimeZone;
+
+    use testing::FixtureServer;
+
     use super::*;
 
-    const CHAIN_LINK_SMART_CONTRACT_ADDR: &'static str = "0x514910771af9ca656af840dff83e8264ecf986ca";
+    const CHAIN_LINK_SMART_CONTRACT_ADDR: &str = "0x514910771af9ca656af840dff83e8264ecf986ca";
+    const DEFAULT_TEST_ADDR: &str = "0xddbd2b932c763ba5b1b7ae3b362eac3e8d40121a";
 
-    fn read_addr_from_env() -> String {
-        match std::env::var("TEST_ADDR") {
-            Ok(val) => val,
-            Err(e) => panic!("couldn't read test addr, did you forget to set the env TEST_ADDR? {}", e)
+    /// An API replaying the fixtures, see the testing module on how to record new ones.
+    pub(crate) fn test_api() -> API {
+        let server = FixtureServer::start(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), Chain::Mainnet).unwrap();
+        let api_token = std::env::var(ETHERSCANIO_API_TOKEN).unwrap_or_else(|_| "test".into());
+        API::new(&api_token).with_base_url(&server.base_url())
+    }
+
+    /// The fixtures are recorded for `DEFAULT_TEST_ADDR`, `TEST_ADDR` only applies when recording.
+    fn test_addr() -> String {
+        match std::env::var(testing::ETHERSCANIO_RECORD) {
+            Ok(_) => std::env::var("TEST_ADDR").unwrap_or_else(|_| DEFAULT_TEST_ADDR.into()),
+            Err(_) => DEFAULT_TEST_ADDR.into(),
         }
     }
 
     #[tokio::test]
     async fn query_balance() {
-        let api = API::new_from_env().unwrap();
-        match api.acc_balance(&read_addr_from_env()).await {
-            Ok(balance) => {
-                println!("got balance: {}", balance);
-            }
-            Err(e) => {
-                println!("error occurred while fetching balance: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let balance = api.acc_balance(&test_addr()).await.unwrap();
+        assert_eq!(balance, 40_891_626_854_930_000_000_000);
     }
 
     #[tokio::test]
     async fn query_chainlink_total_supply() {
-        let api = API::new_from_env().unwrap();
-        match api.erc20_token_total_supply(CHAIN_LINK_SMART_CONTRACT_ADDR).await {
-            Ok(supply) => {
-                println!("chainlink has a total supply of {}", supply);
-            }
-            Err(e) => {
-                println!("error occurred while fetching chainlink total supply: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let supply = api.erc20_token_total_supply(CHAIN_LINK_SMART_CONTRACT_ADDR).await.unwrap();
+        assert_eq!(supply, 1_000_000_000_000_000_000_000_000_000);
     }
 
     #[tokio::test]
     async fn query_gas_oracle() {
-        let api = API::new_from_env().unwrap();
-        match api.gas_oracle().await {
-            Ok(gas_oracle) => {
-                println!("gas oracle: {:?}", gas_oracle);
-            }
-            Err(e) => {
-                println!("error occurred while fetching gas oracle: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let gas_oracle = api.gas_oracle().await.unwrap();
+        assert_eq!(gas_oracle.last_block(), 13_053_741);
+        assert_eq!(gas_oracle.safe_gas_price(), 20_000_000_000);
+        assert_eq!(gas_oracle.fast_gas_price(), 24_000_000_000);
+        assert_eq!(gas_oracle.suggest_base_fee(), 19_230_609_716);
+        assert_eq!(gas_oracle.gas_used_ratio().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn query_daily_avg_gas_price() {
+        let api = test_api();
+        let prices = api.daily_avg_gas_price(NaiveDate::from_ymd_opt(2019, 2, 1).unwrap(), NaiveDate::from_ymd_opt(2019, 2, 28).unwrap()).await.unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].utc_date(), NaiveDate::from_ymd_opt(2019, 2, 1).unwrap());
+        assert_eq!(prices[0].value().avg_gas_price, 13_234_562_600);
+    }
+
+    #[tokio::test]
+    async fn query_price_at() {
+        let api = test_api();
+        let timestamp = Utc.timestamp_opt(1_550_000_000, 0).unwrap();
+        assert_eq!(api.price_at(timestamp).await.unwrap(), 120.67);
+        // served from the cached series
+        let next_day = Utc.timestamp_opt(1_550_016_000, 0).unwrap();
+        assert_eq!(api.price_at(next_day).await.unwrap(), 119.51);
+        // the fetched year has no price for the day, which isn't asked for again
+        let missing_day = Utc.with_ymd_and_hms(2019, 6, 1, 0, 0, 0).unwrap();
+        assert!(api.price_at(missing_day).await.unwrap_err().is::<PriceNotAvailableError>());
+        assert_eq!(api.daily_prices_fetched.lock().unwrap()[&2019], NaiveDate::from_ymd_opt(2019, 12, 31).unwrap());
+
+        let tomorrow = Utc::now() + chrono::Duration::days(1);
+        assert!(api.price_at(tomorrow).await.unwrap_err().is::<PriceNotAvailableError>());
+        let next_year = Utc::now() + chrono::Duration::days(366);
+        assert!(api.price_at(next_year).await.unwrap_err().is::<PriceNotAvailableError>());
+    }
+
+    #[tokio::test]
+    async fn query_eth_supply_breakdown() {
+        let api = test_api();
+        let supply = api.eth_supply_breakdown().await.unwrap();
+        assert_eq!(supply.eth_supply, 122_373_866_217_800_000_000_000_000);
+        assert_eq!(supply.burnt_fees, 3_102_505_506_455_601_519_229_842);
     }
 
     #[tokio::test]
     async fn query_eth_price() {
-        let api = API::new_from_env().unwrap();
-        match api.eth_price().await {
-            Ok(eth_price) => {
-                println!("gas oracle: {:?}", eth_price);
-            }
-            Err(e) => {
-                println!("error occurred while fetching ETH price: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let eth_price = api.eth_price().await.unwrap();
+        assert_eq!(eth_price.eth_usd(), 2149.18);
+        assert_eq!(eth_price.eth_usd_timestamp(), Utc.timestamp_opt(1_624_961_308, 0).unwrap());
     }
 
     #[tokio::test]
     async fn query_txs() {
-        let api = API::new_from_env().unwrap();
-        match api.txs_on_account(&read_addr_from_env()).await {
-            Ok(txs) => {
-                println!("got {} txs", txs.len());
-                for tx in txs {
-                    println!("{:?}", tx);
-                }
-            }
-            Err(e) => {
-                println!("error occurred while fetching txs: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let txs = api.txs_on_account(&test_addr()).await.unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].to(), None);
+        assert_eq!(txs[0].contract_address(), Some("0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc"));
+        assert_eq!(txs[1].value(), 1_000_000_000_000_000_000);
+        assert_eq!(txs[1].fee(), Some(U256::from(34_527u64 * 68_410_000_000)));
     }
 
     #[tokio::test]
     async fn query_internal_txs() {
-        let api = API::new_from_env().unwrap();
-        match api.internal_txs_on_account(&read_addr_from_env()).await {
-            Ok(txs) => {
-                println!("got {} internal txs", txs.len());
-                for tx in txs {
-                    println!("{:?}", tx);
-                }
-            }
-            Err(e) => {
-                println!("error occurred while fetching internal txs: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let txs = api.internal_txs_on_account(&test_addr()).await.unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].value(), 250_000_000_000_000_000);
+        assert_eq!(txs[0].err_code(), None);
+        assert!(!txs[0].is_error());
     }
 
     #[tokio::test]
     async fn erc20_token_transfer_events() {
-        let api = API::new_from_env().unwrap();
-        match api.erc20_transfer_events_on_account(&read_addr_from_env()).await {
-            Ok(erc20_transfer_events) => {
-                println!("got {} ERC20 transfer events", erc20_transfer_events.len());
-                for erc20_transfer_event in erc20_transfer_events {
-                    println!("{:?}", erc20_transfer_event);
-                }
-            }
-            Err(e) => {
-                println!("error occurred while fetching ERC20 transfer events: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let erc20_transfer_events = api.erc20_transfer_events_on_account(&test_addr()).await.unwrap();
+        assert_eq!(erc20_transfer_events.len(), 2);
+        assert_eq!(erc20_transfer_events[0].amount().to_string(), "1 LINK");
+        assert_eq!(erc20_transfer_events[1].amount().to_string(), "2.5 USDC");
     }
 
     #[tokio::test]
     async fn erc20_token_transfer_events_by_contract_addr() {
-        let api = API::new_from_env().unwrap();
-        match api.erc20_transfers_on_account_by_contract(&read_addr_from_env(), CHAIN_LINK_SMART_CONTRACT_ADDR).await {
-            Ok(erc20_transfer_events) => {
-                println!("got {} ERC20 transfer events", erc20_transfer_events.len());
-                for erc20_transfer_event in erc20_transfer_events {
-                    println!("{:?}", erc20_transfer_event);
-                }
-            }
-            Err(e) => {
-                println!("error occurred while fetching ERC20 transfer events by contract addr: {:?}", e);
-            }
-        }
+        let api = test_api();
+        let erc20_transfer_events = api.erc20_transfers_on_account_by_contract(&test_addr(), CHAIN_LINK_SMART_CONTRACT_ADDR).await.unwrap();
+        assert_eq!(erc20_transfer_events.len(), 1);
+        assert_eq!(erc20_transfer_events[0].contract_address(), CHAIN_LINK_SMART_CONTRACT_ADDR);
     }
 
     #[tokio::test]
     async fn erc721_token_transfer_events() {
-        let api = API::new_from_env().unwrap();
-        match api.erc271_transfers_on_account(&read_addr_from_env()).await {
-            Ok(erc721_transfer_events) => {
-                println!("got {} ERC721 transfer events", erc721_transfer_events.len());
-                for erc721_transfer_event in erc721_transfer_events {
-                    println!("{:?}", erc721_transfer_event);
-                }
-            }
-            Err(e) => {
-                println!("error occurred while fetching ERC721 transfer events: {:?}", e);
+        let api = test_api();
+        let erc721_transfer_events = api.erc271_transfers_on_account(&test_addr()).await.unwrap();
+        assert_eq!(erc721_transfer_events.len(), 1);
+        assert_eq!(erc721_transfer_events[0].token_symbol(), "CK");
+    }
+
+    #[tokio::test]
+    async fn account_activity() {
+        let api = test_api();
+        let activity = api.account_activity(&test_addr(), ..).await.unwrap();
+        assert_eq!(activity.len(), 2);
+        assert!(activity[0].transaction().unwrap().contract_address().is_some());
+        assert!(activity[0].erc20_transfers().is_empty());
+        assert_eq!(activity[1].hash(), "0xaa45b4858ba44230a5fce5a29570a5dec2bf1f0ba95bacdec4fe8f2c4fa99338");
+        assert_eq!(activity[1].transaction().unwrap().value(), 1_000_000_000_000_000_000);
+        assert_eq!(activity[1].internal_txs().len(), 1);
+        assert_eq!(activity[1].erc20_transfers().len(), 2);
+        assert_eq!(activity[1].erc721_transfers().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn account_activity_bounds() {
+        let api = test_api();
+        let addr = test_addr();
+        // empty ranges are answered without a request, which would miss the fixtures
+        assert!(api.account_activity(&addr, ..0).await.unwrap().is_empty());
+        assert!(api.account_activity(&addr, (Bound::Included(5), Bound::Excluded(0))).await.unwrap().is_empty());
+        assert!(api.account_activity(&addr, (Bound::Included(5), Bound::Included(4))).await.unwrap().is_empty());
+        // block 0 alone is requested as such, not as the whole history
+        assert!(api.account_activity(&addr, ..=0).await.unwrap().is_empty());
+        assert!(api.account_activity(&addr, 0..=0).await.unwrap().is_empty());
+        assert!(api.account_activity(&addr, 0..1).await.unwrap().is_empty());
+        assert_eq!(api.account_activity(&addr, 0..).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn mined_blocks() {
+        let api = test_api();
+        let mined_blocks = api.mined_blocks_by_account(&test_addr()).await.unwrap();
+        assert_eq!(mined_blocks.len(), 2);
+        assert_eq!(mined_blocks[0].block_number(), 3_462_296);
+        assert_eq!(mined_blocks[0].block_rewards(), 5_194_770_940_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn wait_for_mined_tx() {
+        let api = test_api();
+        let hash = "0xaa45b4858ba44230a5fce5a29570a5dec2bf1f0ba95bacdec4fe8f2c4fa99338";
+        match api.wait_for_tx(hash, 12, Duration::from_secs(1)).await.unwrap() {
+            TxOutcome::Mined { receipt, status } => {
+                assert_eq!(receipt.block_number(), 14_923_692);
+                assert_eq!(receipt.gas_used(), 34_527);
+                assert_eq!(status, ReceiptStatus::Pass);
             }
+            outcome => panic!("unexpected outcome {:?}", outcome),
         }
     }
 
     #[tokio::test]
-    async fn mined_blocks() {
-        let api = API::new_from_env().unwrap();
-        match api.mined_blocks_by_account(&read_addr_from_env()).await {
-            Ok(mined_blocks) => {
-                println!("got {} mined blocks", mined_blocks.len());
-                for mined_block in mined_blocks {
-                    println!("{:?}", mined_block);
-                }
+    async fn wait_for_failed_tx() {
+        let api = test_api();
+        let hash = "0x15f8e5ea1079d9a0bb04a4c58ae5fe7654b5b2b4463375ff7ffb490aa0032f3a";
+        match api.wait_for_tx(hash, 12, Duration::from_secs(1)).await.unwrap() {
+            TxOutcome::Mined { status, .. } => assert_eq!(status, ReceiptStatus::Fail { description: Some("Reverted".into()) }),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+        // the tx has 257 confirmations
+        assert!(api.wait_for_tx(hash, 1000, Duration::from_millis(10)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn wait_for_dropped_tx() {
+        let api = test_api();
+        // neither a receipt nor the tx is known
+        let hash = "0x00000000000000000000000000000000000000000000000000000000000000d1";
+        assert!(matches!(api.wait_for_tx(hash, 1, Duration::from_millis(10)).await.unwrap(), TxOutcome::Dropped));
+    }
+
+    #[test]
+    fn replaced_once_nonce_is_used() {
+        // a count of 5 means nonces 0 to 4 are used
+        assert!(!nonce_used(5, 5));
+        assert!(nonce_used(6, 5));
+    }
+
+    #[tokio::test]
+    async fn query_with_key_pool() {
+        let api = test_api().with_key_pool(KeyPool::new(&["a", "b"], 5));
+        assert_eq!(api.acc_balance(&test_addr()).await.unwrap(), 40_891_626_854_930_000_000_000);
+        assert_eq!(api.request_interval(), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn decodes_input_with_fetched_abi_once() {
+        let api = test_api();
+        let transfer = "0xa9059cbb0000000000000000000000004e83362442b8d1bec281594cea3050c8eb01311c0000000000000000000000000000000000000000000000000de0b6b3a7640000";
+        // decoded with the known functions, without fetching the ABI
+        assert_eq!(api.decode_input("0x0000000000000000000000000000000000000002", transfer).await.unwrap().name, "transfer");
+
+        let unverified = "0x0000000000000000000000000000000000000001";
+        let unknown = "0x12345678";
+        assert!(api.decode_input(unverified, unknown).await.is_err());
+        assert!(matches!(api.abis.lock().unwrap().get(unverified), Some(Err(_))));
+        // answered from the remembered failure
+        assert!(api.decode_input(unverified, unknown).await.unwrap_err().to_string().contains("not verified"));
+    }
+
+    #[tokio::test]
+    async fn only_reqwest_logs_the_api_key() {
+        struct Capture(Arc<Mutex<Vec<(String, String)>>>);
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
             }
-            Err(e) => {
-                println!("error occurred while fetching mined blocks: {:?}", e);
+
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push((record.target().to_string(), record.args().to_string()));
             }
+
+            fn flush(&self) {}
+        }
+        let logs = Arc::new(Mutex::new(vec![]));
+        log::set_boxed_logger(Box::new(Capture(logs.clone()))).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let server = FixtureServer::start(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), Chain::Mainnet).unwrap();
+        let api = API::new("LOGGEDKEY").with_base_url(&server.base_url());
+        api.acc_balance(DEFAULT_TEST_ADDR).await.unwrap();
+        let logs = logs.lock().unwrap();
+        let leaking: Vec<&String> = logs.iter().filter(|(_, message)| message.contains("LOGGEDKEY")).map(|(target, _)| target).collect();
+        assert!(!leaking.is_empty());
+        assert!(leaking.iter().all(|target| target.starts_with("reqwest")), "{:?}", leaking);
+    }
+
+    #[test]
+    fn redacts_api_key() {
+        assert_eq!(redact_api_key("error sending request for url (https://api.etherscan.io/api?module=account&apikey=SECRET&action=balance): timed out"),
+                   "error sending request for url (https://api.etherscan.io/api?module=account&apikey=***&action=balance): timed out");
+        assert_eq!(redact_api_key("http://localhost/api?apikey=SECRET"), "http://localhost/api?apikey=***");
+        assert!(!format!("{:?}", API::new("SECRET")).contains("SECRET"));
+    }
+
+    #[tokio::test]
+    async fn request_errors_hide_api_key() {
+        // nothing listens on the discard port
+        let api = API::new("SECRET").with_base_url("http://127.0.0.1:9/api");
+        let error = api.acc_balance(&test_addr()).await.unwrap_err();
+        assert!(!error.to_string().contains("SECRET"));
+        assert!(!format!("{:?}", error).contains("SECRET"));
+    }
+
+    #[test]
+    fn empty_list_is_no_error() {
+        let response: Response<Vec<Transaction>> = serde_json::from_str(r#"{"status":"0","message":"No transactions found","result":[]}"#).unwrap();
+        assert!(response.result_or_error().unwrap().is_empty());
+        let response: Response<String> = serde_json::from_str(r#"{"status":"0","message":"NOTOK","result":"Invalid API Key"}"#).unwrap();
+        assert!(response.result_or_error().is_err());
+    }
+
+    struct TokenSupply<'a>(&'a str);
+
+    impl Endpoint for TokenSupply<'_> {
+        type Response = String;
+
+        fn module(&self) -> &str {
+            "stats"
         }
+
+        fn action(&self) -> &str {
+            "tokensupply"
+        }
+
+        fn params(&self) -> Vec<(String, String)> {
+            vec![("contractaddress".into(), self.0.into())]
+        }
+    }
+
+    #[tokio::test]
+    async fn call_custom_endpoint() {
+        let api = test_api();
+        let supply = api.call(TokenSupply(CHAIN_LINK_SMART_CONTRACT_ADDR)).await.unwrap();
+        assert_eq!(supply, "1000000000000000000000000000");
+    }
+
+    #[tokio::test]
+    async fn call_raw() {
+        let api = test_api();
+        assert_eq!(api.raw("proxy", "eth_blockNumber", &[]).await.unwrap(), "0xe3c000");
+        let supply = api.raw("stats", "tokensupply", &[("contractaddress", CHAIN_LINK_SMART_CONTRACT_ADDR)]).await.unwrap();
+        assert_eq!(supply, "1000000000000000000000000000");
+    }
+
+    #[derive(Default)]
+    struct RecordedMetrics {
+        counters: Mutex<HashMap<String, u64>>,
+        histograms: Mutex<Vec<String>>,
+    }
+
+    impl Metrics for RecordedMetrics {
+        fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+            *self.counters.lock().unwrap().entry(format!("{}{:?}", name, labels)).or_default() += value;
+        }
+
+        fn record_histogram(&self, name: &str, _labels: &[(&str, &str)], _value: f64) {
+            self.histograms.lock().unwrap().push(name.into());
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_metrics() {
+        let metrics = Arc::new(RecordedMetrics::default());
+        let api = test_api().with_cache(Arc::new(cache::MemoryCache::new())).with_metrics(metrics.clone());
+        api.eth_price().await.unwrap();
+        api.eth_price().await.unwrap();
+        assert!(api.acc_balance("0x0000000000000000000000000000000000000000").await.is_err());
+
+        let counters = metrics.counters.lock().unwrap();
+        let labels = r#"[("module", "stats"), ("action", "ethprice"), ("chain", "mainnet")"#;
+        assert_eq!(counters[&format!(r#"{}{}, ("status", "ok")]"#, metrics::REQUESTS, labels)], 2);
+        assert_eq!(counters[&format!("{}{}]", metrics::CACHE_HITS, labels)], 1);
+        assert_eq!(counters[&format!(r#"{}[("module", "account"), ("action", "balance"), ("chain", "mainnet"), ("status", "error")]"#, metrics::REQUESTS)], 1);
+        assert_eq!(metrics.histograms.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn refetches_unreadable_cache_entry() {
+        let cache = Arc::new(cache::MemoryCache::new());
+        let api = test_api().with_cache(cache.clone());
+        let key = cache::cache_key(&format!("{}?module=stats&action=ethprice", api.base_url));
+        cache.set(&key, r#"{"status":"1","message":"OK","res"#, None);
+        assert_eq!(api.eth_price().await.unwrap().eth_usd(), 2149.18);
+        assert!(serde_json::from_str::<serde_json::Value>(&cache.get(&key).unwrap()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn missing_fixture_fails() {
+        let api = test_api();
+        assert!(api.acc_balance("0x0000000000000000000000000000000000000000").await.is_err());
     }
 }
@@ -0,0 +1,129 @@
+//! Request metrics. Every call of an endpoint is reported to the `Metrics` set with
+//! `API::with_metrics`, labelled with `module`, `action` and `chain`.
+
+/// Calls, labelled with their `status`, `ok` or `error`.
+pub const REQUESTS: &str = "etherscan_requests_total";
+/// Requests repeated with another key of the key pool.
+pub const RETRIES: &str = "etherscan_retries_total";
+/// Calls answered from the cache.
+pub const CACHE_HITS: &str = "etherscan_cache_hits_total";
+/// The duration of calls in seconds, including retries and waiting for the rate limit.
+pub const REQUEST_DURATION: &str = "etherscan_request_duration_seconds";
+
+/// Receives the metrics of the calls, e.g. to forward them to a metrics library.
+pub trait Metrics: Send + Sync {
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64);
+
+    fn record_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64);
+}
+
+#[cfg(feature = "prometheus")]
+pub use self::prometheus_metrics::PrometheusMetrics;
+
+#[cfg(feature = "prometheus")]
+mod prometheus_metrics {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+
+    use super::Metrics;
+
+    /// Registers the metrics in a Prometheus registry as they are first reported, behind the
+    /// `prometheus` feature.
+    pub struct PrometheusMetrics {
+        registry: Registry,
+        counters: Mutex<HashMap<String, IntCounterVec>>,
+        histograms: Mutex<HashMap<String, HistogramVec>>,
+    }
+
+    impl PrometheusMetrics {
+        pub fn new(registry: Registry) -> PrometheusMetrics {
+            PrometheusMetrics {
+                registry,
+                counters: Mutex::new(HashMap::new()),
+                histograms: Mutex::new(HashMap::new()),
+            }
+        }
+
+        pub fn registry(&self) -> &Registry {
+            &self.registry
+        }
+    }
+
+    fn help(name: &str) -> &'static str {
+        match name {
+            super::REQUESTS => "Etherscan API calls",
+            super::RETRIES => "Etherscan API requests retried with another key",
+            super::CACHE_HITS => "Etherscan API calls answered from the cache",
+            super::REQUEST_DURATION => "Duration of Etherscan API calls in seconds",
+            _ => "Etherscan API metric",
+        }
+    }
+
+    fn split<'a>(labels: &[(&'a str, &'a str)]) -> (Vec<&'a str>, Vec<&'a str>) {
+        labels.iter().cloned().unzip()
+    }
+
+    impl Metrics for PrometheusMetrics {
+        fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+            let (names, values) = split(labels);
+            let mut counters = self.counters.lock().unwrap();
+            if !counters.contains_key(name) {
+                let counter = match IntCounterVec::new(Opts::new(name, help(name)), &names) {
+                    Ok(counter) => counter,
+                    Err(_) => return,
+                };
+                // a metric of the same name registered by someone else is left alone
+                if self.registry.register(Box::new(counter.clone())).is_err() {
+                    return;
+                }
+                counters.insert(name.into(), counter);
+            }
+            if let Ok(counter) = counters[name].get_metric_with_label_values(&values) {
+                counter.inc_by(value);
+            }
+        }
+
+        fn record_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+            let (names, values) = split(labels);
+            let mut histograms = self.histograms.lock().unwrap();
+            if !histograms.contains_key(name) {
+                let histogram = match HistogramVec::new(HistogramOpts::new(name, help(name)), &names) {
+                    Ok(histogram) => histogram,
+                    Err(_) => return,
+                };
+                if self.registry.register(Box::new(histogram.clone())).is_err() {
+                    return;
+                }
+                histograms.insert(name.into(), histogram);
+            }
+            if let Ok(histogram) = histograms[name].get_metric_with_label_values(&values) {
+                histogram.observe(value);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use prometheus::{Encoder, TextEncoder};
+
+        use super::super::{REQUESTS, REQUEST_DURATION};
+        use super::*;
+
+        #[test]
+        fn exports_text_format() {
+            let metrics = PrometheusMetrics::new(Registry::new());
+            let labels = [("module", "account"), ("action", "balance"), ("chain", "mainnet")];
+            metrics.increment_counter(REQUESTS, &[labels[0], labels[1], labels[2], ("status", "ok")], 1);
+            metrics.increment_counter(REQUESTS, &[labels[0], labels[1], labels[2], ("status", "ok")], 1);
+            metrics.record_histogram(REQUEST_DURATION, &labels, 0.2);
+
+            let mut text = vec![];
+            TextEncoder::new().encode(&metrics.registry().gather(), &mut text).unwrap();
+            let text = String::from_utf8(text).unwrap();
+            assert!(text.contains(r#"etherscan_requests_total{action="balance",chain="mainnet",module="account",status="ok"} 2"#), "{}", text);
+            assert!(text.contains(r#"etherscan_request_duration_seconds_count{action="balance",chain="mainnet",module="account"} 1"#), "{}", text);
+        }
+    }
+}
@@ -1,9 +1,16 @@
+use std::fmt;
 use std::fmt::Debug;
 use std::num::ParseIntError;
+use std::ops::Deref;
+use std::str::FromStr;
 
+use chrono::{DateTime, NaiveDate, Utc};
+use primitive_types::U256;
 use serde::{Deserialize, Serialize};
 
+use super::abi::{decode_hex, decode_topic, Abi, AbiError, DecodedLog};
 use super::format::*;
+use super::units::{GweiAmount, TokenAmount};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Balance(String);
@@ -28,28 +35,135 @@ pub struct Transaction {
     #[serde(deserialize_with = "from_str", rename(deserialize = "transactionIndex"))]
     transaction_index: u64,
     from: String,
-    to: String,
-    #[serde(deserialize_with = "from_str")]
-    value: i64,
+    /// Empty for contract creations.
+    #[serde(deserialize_with = "from_optional_str")]
+    to: Option<String>,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str")]
+    value: u128,
     #[serde(deserialize_with = "from_str")]
-    gas: i64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "gasPrice"))]
-    gas_price: i64,
-    #[serde(rename(deserialize = "isError"))]
-    is_error: String,
-    #[serde(rename(deserialize = "txreceipt_status"))]
-    tx_receipt_status: String,
+    gas: u64,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "gasPrice"))]
+    gas_price: u128,
+    #[serde(default, serialize_with = "to_optional_str", deserialize_with = "from_optional_str", rename(deserialize = "maxFeePerGas"))]
+    max_fee_per_gas: Option<u128>,
+    #[serde(default, serialize_with = "to_optional_str", deserialize_with = "from_optional_str", rename(deserialize = "maxPriorityFeePerGas"))]
+    max_priority_fee_per_gas: Option<u128>,
+    #[serde(default, serialize_with = "to_optional_str", deserialize_with = "from_optional_str", rename(deserialize = "effectiveGasPrice"))]
+    effective_gas_price: Option<u128>,
+    #[serde(deserialize_with = "from_bool_str", rename(deserialize = "isError"))]
+    is_error: bool,
+    /// Empty for transactions mined before Byzantium, which have no receipt status.
+    #[serde(deserialize_with = "from_optional_str", rename(deserialize = "txreceipt_status"))]
+    tx_receipt_status: Option<ReceiptStatus>,
     input: String,
-    #[serde(rename(deserialize = "contractAddress"))]
-    contract_address: String,
+    /// Only set for contract creations.
+    #[serde(deserialize_with = "from_optional_str", rename(deserialize = "contractAddress"))]
+    contract_address: Option<String>,
     #[serde(deserialize_with = "from_str", rename(deserialize = "cumulativeGasUsed"))]
     cumulative_gas_used: u64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "gasUsed"))]
-    gas_used: String,
+    #[serde(deserialize_with = "from_optional_str", rename(deserialize = "gasUsed"))]
+    gas_used: Option<u64>,
     #[serde(deserialize_with = "from_str")]
     confirmations: u64,
 }
 
+impl Transaction {
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn block_hash(&self) -> &str {
+        &self.block_hash
+    }
+
+    pub fn transaction_index(&self) -> u64 {
+        self.transaction_index
+    }
+
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    /// `None` for contract creations.
+    pub fn to(&self) -> Option<&str> {
+        self.to.as_deref()
+    }
+
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    pub fn gas(&self) -> u64 {
+        self.gas
+    }
+
+    pub fn cumulative_gas_used(&self) -> u64 {
+        self.cumulative_gas_used
+    }
+
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// The address of the created contract for contract creations.
+    pub fn contract_address(&self) -> Option<&str> {
+        self.contract_address.as_deref()
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.is_error
+    }
+
+    /// `None` for transactions mined before Byzantium.
+    pub fn receipt_status(&self) -> Option<&ReceiptStatus> {
+        self.tx_receipt_status.as_ref()
+    }
+
+    /// `None` where Etherscan reports it empty.
+    pub fn gas_used(&self) -> Option<u64> {
+        self.gas_used
+    }
+
+    /// The gas price in wei. For EIP-1559 transactions Etherscan reports the effective gas price here.
+    pub fn gas_price(&self) -> u128 {
+        self.gas_price
+    }
+
+    pub fn max_fee_per_gas(&self) -> Option<u128> {
+        self.max_fee_per_gas
+    }
+
+    pub fn max_priority_fee_per_gas(&self) -> Option<u128> {
+        self.max_priority_fee_per_gas
+    }
+
+    /// The price per gas actually paid, falling back to `gas_price` when it isn't reported separately.
+    pub fn effective_gas_price(&self) -> u128 {
+        self.effective_gas_price.unwrap_or(self.gas_price)
+    }
+
+    /// The fee paid for the transaction in wei, `None` without the gas used.
+    pub fn fee(&self) -> Option<U256> {
+        self.gas_used.map(|gas_used| U256::from(gas_used) * U256::from(self.effective_gas_price()))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct InternalTransaction {
     #[serde(deserialize_with = "from_str", rename(deserialize = "blockNumber"))]
@@ -58,24 +172,86 @@ pub struct InternalTransaction {
     timestamp: u64,
     hash: String,
     from: String,
-    to: String,
-    #[serde(deserialize_with = "from_str")]
+    /// Empty for contract creations.
+    #[serde(deserialize_with = "from_optional_str")]
+    to: Option<String>,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str")]
     value: u128,
-    #[serde(rename(deserialize = "contractAddress"))]
-    contract_address: String,
+    /// Only set for contract creations.
+    #[serde(deserialize_with = "from_optional_str", rename(deserialize = "contractAddress"))]
+    contract_address: Option<String>,
     input: String,
     #[serde(rename(deserialize = "type"))]
     tx_type: String,
     #[serde(deserialize_with = "from_str")]
     gas: u64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "gasUsed"))]
-    gas_used: String,
+    #[serde(deserialize_with = "from_optional_str", rename(deserialize = "gasUsed"))]
+    gas_used: Option<u64>,
     #[serde(deserialize_with = "from_str", rename(deserialize = "traceId"))]
     trace_id: u64,
-    #[serde(rename(deserialize = "isError"))]
-    is_error: String,
-    #[serde(rename(deserialize = "errCode"))]
-    err_code: String,
+    #[serde(deserialize_with = "from_bool_str", rename(deserialize = "isError"))]
+    is_error: bool,
+    #[serde(deserialize_with = "from_optional_str", rename(deserialize = "errCode"))]
+    err_code: Option<String>,
+}
+
+impl InternalTransaction {
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    /// `None` for contract creations.
+    pub fn to(&self) -> Option<&str> {
+        self.to.as_deref()
+    }
+
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn tx_type(&self) -> &str {
+        &self.tx_type
+    }
+
+    pub fn gas(&self) -> u64 {
+        self.gas
+    }
+
+    pub fn trace_id(&self) -> u64 {
+        self.trace_id
+    }
+
+    pub fn contract_address(&self) -> Option<&str> {
+        self.contract_address.as_deref()
+    }
+
+    pub fn gas_used(&self) -> Option<u64> {
+        self.gas_used
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.is_error
+    }
+
+    pub fn err_code(&self) -> Option<&str> {
+        self.err_code.as_deref()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -93,7 +269,7 @@ pub struct ERC20TokenTransferEvent {
     #[serde(rename(deserialize = "contractAddress"))]
     contract_address: String,
     to: String,
-    #[serde(deserialize_with = "from_str")]
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str")]
     value: u128,
     #[serde(rename(deserialize = "tokenName"))]
     token_name: String,
@@ -103,12 +279,14 @@ pub struct ERC20TokenTransferEvent {
     token_decimal: u64,
     #[serde(rename(deserialize = "transactionIndex"), deserialize_with = "from_str")]
     transaction_index: u64,
+    #[serde(default, deserialize_with = "from_optional_str", rename(deserialize = "logIndex"))]
+    log_index: Option<u64>,
     #[serde(deserialize_with = "from_str")]
     gas: u64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "gasPrice"))]
-    gas_price: u64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "gasUsed"))]
-    gas_used: String,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "gasPrice"))]
+    gas_price: u128,
+    #[serde(deserialize_with = "from_optional_str", rename(deserialize = "gasUsed"))]
+    gas_used: Option<u64>,
     #[serde(deserialize_with = "from_str", rename(deserialize = "cumulativeGasUsed"))]
     cumulative_gas_used: u64,
     input: String,
@@ -116,7 +294,197 @@ pub struct ERC20TokenTransferEvent {
     confirmations: u64,
 }
 
-pub type ERC721TokenTransferEvent = ERC20TokenTransferEvent;
+impl ERC20TokenTransferEvent {
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn block_hash(&self) -> &str {
+        &self.block_hash
+    }
+
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+
+    pub fn contract_address(&self) -> &str {
+        &self.contract_address
+    }
+
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    pub fn token_name(&self) -> &str {
+        &self.token_name
+    }
+
+    pub fn token_symbol(&self) -> &str {
+        &self.token_symbol
+    }
+
+    pub fn token_decimal(&self) -> u64 {
+        self.token_decimal
+    }
+
+    pub fn transaction_index(&self) -> u64 {
+        self.transaction_index
+    }
+
+    /// The index of the transfer's log in its block, `None` where Etherscan doesn't send it.
+    pub fn log_index(&self) -> Option<u64> {
+        self.log_index
+    }
+
+    /// Identifies the transfer by tx hash and log index. Without a log index, it falls back to the
+    /// content of the transfer, which can't tell identical transfers of one tx apart.
+    pub(crate) fn transfer_id(&self) -> String {
+        match self.log_index {
+            Some(log_index) => format!("{}:{}", self.hash, log_index),
+            None => format!("{}:{}:{}:{}:{}", self.hash, self.contract_address, self.from, self.to, self.value),
+        }
+    }
+
+    pub fn gas(&self) -> u64 {
+        self.gas
+    }
+
+    pub fn cumulative_gas_used(&self) -> u64 {
+        self.cumulative_gas_used
+    }
+
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// `None` where Etherscan reports it empty.
+    pub fn gas_used(&self) -> Option<u64> {
+        self.gas_used
+    }
+
+    pub fn gas_price(&self) -> u128 {
+        self.gas_price
+    }
+
+    /// The fee paid for the transaction that emitted the transfer in wei, `None` without the gas used.
+    pub fn fee(&self) -> Option<U256> {
+        self.gas_used.map(|gas_used| U256::from(gas_used) * U256::from(self.gas_price))
+    }
+
+    /// The transferred amount scaled by the token's decimals, e.g. `12.5 LINK`.
+    pub fn amount(&self) -> TokenAmount {
+        TokenAmount::new(self.value, self.token_decimal as u32, &self.token_symbol)
+    }
+}
+
+/// A transfer of an ERC721 token. Etherscan reports it in the format of an ERC20 transfer, whose
+/// accessors it derefs to.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct ERC721TokenTransferEvent(ERC20TokenTransferEvent);
+
+impl Deref for ERC721TokenTransferEvent {
+    type Target = ERC20TokenTransferEvent;
+
+    fn deref(&self) -> &ERC20TokenTransferEvent {
+        &self.0
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Log {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    #[serde(deserialize_with = "from_hex_or_dec_str", rename(deserialize = "blockNumber"))]
+    block_number: u64,
+    #[serde(deserialize_with = "from_hex_or_dec_str", rename(deserialize = "timeStamp"))]
+    timestamp: u64,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_hex_or_dec_str", rename(deserialize = "gasPrice"))]
+    gas_price: u128,
+    #[serde(deserialize_with = "from_hex_or_dec_str", rename(deserialize = "gasUsed"))]
+    gas_used: u64,
+    #[serde(deserialize_with = "from_hex_or_dec_str", rename(deserialize = "logIndex"))]
+    log_index: u64,
+    #[serde(rename(deserialize = "transactionHash"))]
+    transaction_hash: String,
+    #[serde(deserialize_with = "from_hex_or_dec_str", rename(deserialize = "transactionIndex"))]
+    transaction_index: u64,
+}
+
+impl Log {
+    /// The address of the contract which emitted the log.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn topics(&self) -> &[String] {
+        &self.topics
+    }
+
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn gas_price(&self) -> u128 {
+        self.gas_price
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    pub fn log_index(&self) -> u64 {
+        self.log_index
+    }
+
+    pub fn transaction_hash(&self) -> &str {
+        &self.transaction_hash
+    }
+
+    pub fn transaction_index(&self) -> u64 {
+        self.transaction_index
+    }
+
+    /// Decodes the log with the events of the given ABI.
+    pub fn decode(&self, abi: &Abi) -> Result<DecodedLog, AbiError> {
+        let (topics, data) = self.raw()?;
+        abi.decode_log(&topics, &data)
+    }
+
+    pub(crate) fn raw(&self) -> Result<(Vec<[u8; 32]>, Vec<u8>), AbiError> {
+        let topics = self.topics.iter().map(|t| decode_topic(t)).collect::<Result<Vec<_>, _>>()?;
+        Ok((topics, decode_hex(&self.data)?))
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MinedBlock {
@@ -124,70 +492,549 @@ pub struct MinedBlock {
     block_number: u64,
     #[serde(deserialize_with = "from_str", rename(deserialize = "timeStamp"))]
     timestamp: u64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "blockRewards"))]
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "blockReward"))]
     block_rewards: u128,
 }
 
+impl MinedBlock {
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn block_rewards(&self) -> u128 {
+        self.block_rewards
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TransactionReceiptStatus {
-    #[serde(deserialize_with = "from_str")]
-    status: u64,
+    #[serde(deserialize_with = "from_optional_str")]
+    status: Option<ReceiptStatus>,
 }
 
 impl TransactionReceiptStatus {
-    pub fn status(&self) -> ReceiptStatus {
-        match self.status {
-            1 => ReceiptStatus::Pass,
-            _ => ReceiptStatus::Fail,
-        }
+    /// `None` for transactions mined before Byzantium, which have no receipt status.
+    pub fn status(&self) -> Option<ReceiptStatus> {
+        self.status.clone()
     }
 }
 
+#[derive(Serialize, Debug, Clone, PartialEq)]
 pub enum ReceiptStatus {
     Pass,
-    Fail,
+    Fail { description: Option<String> },
+}
+
+impl ReceiptStatus {
+    pub fn is_pass(&self) -> bool {
+        *self == ReceiptStatus::Pass
+    }
+}
+
+impl FromStr for ReceiptStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(ReceiptStatus::Pass),
+            "0" => Ok(ReceiptStatus::Fail { description: None }),
+            _ => Err(format!("invalid receipt status '{}'", s)),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ContractExecutionStatus {
-    #[serde(deserialize_with = "from_str", rename(deserialize = "isError"))]
-    is_error: u64,
+    #[serde(deserialize_with = "from_bool_str", rename(deserialize = "isError"))]
+    is_error: bool,
     #[serde(rename(deserialize = "errDescription"))]
     err_description: String,
 }
 
 impl ContractExecutionStatus {
-    pub fn status(self) -> ExecutionStatus {
-        match self.is_error {
-            0 => ExecutionStatus::Pass,
-            _ => ExecutionStatus::Error { status_code: self.is_error, description: self.err_description },
+    pub fn status(self) -> ReceiptStatus {
+        if !self.is_error {
+            return ReceiptStatus::Pass;
         }
+        let description = if self.err_description.is_empty() { None } else { Some(self.err_description) };
+        ReceiptStatus::Fail { description }
     }
 }
 
-pub enum ExecutionStatus {
-    Pass,
-    Error { status_code: u64, description: String },
+/// A transaction receipt as returned by the proxy module's `eth_getTransactionReceipt`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransactionReceipt {
+    #[serde(rename(deserialize = "transactionHash"))]
+    transaction_hash: String,
+    #[serde(deserialize_with = "from_hex_or_dec_str", rename(deserialize = "blockNumber"))]
+    block_number: u64,
+    #[serde(rename(deserialize = "blockHash"))]
+    block_hash: String,
+    from: String,
+    /// Empty for contract creations.
+    #[serde(default)]
+    to: Option<String>,
+    #[serde(default, rename(deserialize = "contractAddress"))]
+    contract_address: Option<String>,
+    #[serde(deserialize_with = "from_hex_or_dec_str", rename(deserialize = "gasUsed"))]
+    gas_used: u64,
+    #[serde(default, serialize_with = "to_optional_str", deserialize_with = "from_optional_hex_or_dec_str", rename(deserialize = "effectiveGasPrice"))]
+    effective_gas_price: Option<u128>,
+    /// Absent for transactions mined before Byzantium.
+    #[serde(default, deserialize_with = "from_optional_hex_or_dec_str")]
+    status: Option<u64>,
+}
+
+impl TransactionReceipt {
+    pub fn transaction_hash(&self) -> &str {
+        &self.transaction_hash
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    pub fn block_hash(&self) -> &str {
+        &self.block_hash
+    }
+
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    pub fn to(&self) -> Option<&str> {
+        self.to.as_deref()
+    }
+
+    pub fn contract_address(&self) -> Option<&str> {
+        self.contract_address.as_deref()
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    pub fn effective_gas_price(&self) -> Option<u128> {
+        self.effective_gas_price
+    }
+
+    /// `None` for transactions mined before Byzantium. A failure carries no description, the
+    /// revert reason is only available through `contract_execution_status`.
+    pub fn status(&self) -> Option<ReceiptStatus> {
+        self.status.map(|status| if status == 1 { ReceiptStatus::Pass } else { ReceiptStatus::Fail { description: None } })
+    }
+}
+
+/// A transaction as returned by the proxy module's `eth_getTransactionByHash`, pending or mined.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProxyTransaction {
+    hash: String,
+    from: String,
+    #[serde(deserialize_with = "from_hex_or_dec_str")]
+    nonce: u64,
+    /// `None` while pending.
+    #[serde(default, deserialize_with = "from_optional_hex_or_dec_str", rename(deserialize = "blockNumber"))]
+    block_number: Option<u64>,
+}
+
+impl ProxyTransaction {
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn block_number(&self) -> Option<u64> {
+        self.block_number
+    }
+}
+
+/// How a transaction waited for through `API::wait_for_tx` ended.
+#[derive(Debug)]
+pub enum TxOutcome {
+    /// Mined with the requested number of confirmations. A failed status carries the revert reason if
+    /// Etherscan knows it.
+    Mined { receipt: TransactionReceipt, status: ReceiptStatus },
+    /// The sender's nonce was consumed by another transaction.
+    Replaced { from: String, nonce: u64 },
+    /// The transaction vanished, or was never seen, and its nonce is still unused.
+    Dropped,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GasOracle {
     #[serde(deserialize_with = "from_str", rename(deserialize = "LastBlock"))]
-    last_block: u128,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "SafeGasPrice"))]
+    last_block: u64,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_gwei_str", rename(deserialize = "SafeGasPrice"))]
     safe_gas_price: u128,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "ProposeGasPrice"))]
+    #[serde(serialize_with = "to_str", deserialize_with = "from_gwei_str", rename(deserialize = "ProposeGasPrice"))]
     propose_gas_price: u128,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_gwei_str", rename(deserialize = "FastGasPrice"))]
+    fast_gas_price: u128,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_gwei_str", rename(deserialize = "suggestBaseFee"))]
+    suggest_base_fee: u128,
+    #[serde(deserialize_with = "from_str_list", rename(deserialize = "gasUsedRatio"))]
+    gas_used_ratio: Vec<f64>,
+}
+
+/// All gas prices are denominated in wei.
+impl GasOracle {
+    pub fn last_block(&self) -> u64 {
+        self.last_block
+    }
+
+    pub fn safe_gas_price(&self) -> u128 {
+        self.safe_gas_price
+    }
+
+    pub fn propose_gas_price(&self) -> u128 {
+        self.propose_gas_price
+    }
+
+    pub fn fast_gas_price(&self) -> u128 {
+        self.fast_gas_price
+    }
+
+    pub fn suggest_base_fee(&self) -> u128 {
+        self.suggest_base_fee
+    }
+
+    pub fn safe_gas_price_gwei(&self) -> GweiAmount {
+        GweiAmount(self.safe_gas_price)
+    }
+
+    pub fn propose_gas_price_gwei(&self) -> GweiAmount {
+        GweiAmount(self.propose_gas_price)
+    }
+
+    pub fn fast_gas_price_gwei(&self) -> GweiAmount {
+        GweiAmount(self.fast_gas_price)
+    }
+
+    /// The gas used ratio of the most recent blocks, oldest first.
+    pub fn gas_used_ratio(&self) -> &[f64] {
+        &self.gas_used_ratio
+    }
+}
+
+/// A single entry of one of the daily statistics series, keyed by its UTC date.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyStat<T> {
+    #[serde(rename(deserialize = "UTCDate"))]
+    utc_date: NaiveDate,
+    #[serde(deserialize_with = "from_str", rename(deserialize = "unixTimeStamp"))]
+    timestamp: u64,
+    #[serde(flatten)]
+    value: T,
+}
+
+impl<T> DailyStat<T> {
+    pub fn utc_date(&self) -> NaiveDate {
+        self.utc_date
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyGasLimit {
+    #[serde(deserialize_with = "from_str", rename(deserialize = "gasLimit"))]
+    pub gas_limit: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyGasUsed {
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "gasUsed"))]
+    pub gas_used: u128,
+}
+
+/// Daily gas prices, denominated in wei.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyGasPrice {
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "maxGasPrice_Wei"))]
+    pub max_gas_price: u128,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "minGasPrice_Wei"))]
+    pub min_gas_price: u128,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "avgGasPrice_Wei"))]
+    pub avg_gas_price: u128,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ETHPrice {
-    #[serde(deserialize_with = "from_str", rename(deserialize = "ethbtc"))]
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "ethbtc"))]
     eth_btc: f64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "ethbtc_timestamp"))]
-    eth_btc_timestamp: u64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "ethusd"))]
+    #[serde(deserialize_with = "from_unix_timestamp_str", rename(deserialize = "ethbtc_timestamp"))]
+    eth_btc_timestamp: DateTime<Utc>,
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "ethusd"))]
     eth_usd: f64,
-    #[serde(deserialize_with = "from_str", rename(deserialize = "ethusd_timestamp"))]
-    eth_usd_timestamp: u64,
-}
\ No newline at end of file
+    #[serde(deserialize_with = "from_unix_timestamp_str", rename(deserialize = "ethusd_timestamp"))]
+    eth_usd_timestamp: DateTime<Utc>,
+}
+
+impl ETHPrice {
+    pub fn eth_btc(&self) -> f64 {
+        self.eth_btc
+    }
+
+    pub fn eth_btc_timestamp(&self) -> DateTime<Utc> {
+        self.eth_btc_timestamp
+    }
+
+    pub fn eth_usd(&self) -> f64 {
+        self.eth_usd
+    }
+
+    pub fn eth_usd_timestamp(&self) -> DateTime<Utc> {
+        self.eth_usd_timestamp
+    }
+}
+
+/// The closing price of ether in USD of a given day.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyPrice {
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "value"))]
+    pub price: f64,
+}
+
+/// The total supply of ether split up into its components, denominated in wei.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ETHSupply {
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "EthSupply"))]
+    pub eth_supply: u128,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "Eth2Staking"))]
+    pub eth2_staking: u128,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "BurntFees"))]
+    pub burnt_fees: u128,
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "WithdrawnTotal"))]
+    pub withdrawn_total: u128,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChainSize {
+    #[serde(deserialize_with = "from_str", rename(deserialize = "blockNumber"))]
+    pub block_number: u64,
+    #[serde(rename(deserialize = "chainTimeStamp"))]
+    pub chain_date: NaiveDate,
+    /// The size of the chain data in bytes.
+    #[serde(serialize_with = "to_str", deserialize_with = "from_str", rename(deserialize = "chainSize"))]
+    pub chain_size: u128,
+    #[serde(rename(deserialize = "clientType"))]
+    pub client_type: String,
+    #[serde(rename(deserialize = "syncMode"))]
+    pub sync_mode: String,
+}
+
+/// The networks Etherscan runs an API for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Chain {
+    Mainnet,
+    Goerli,
+    Sepolia,
+    Holesky,
+}
+
+impl Chain {
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            Chain::Mainnet => "https://api.etherscan.io/api",
+            Chain::Goerli => "https://api-goerli.etherscan.io/api",
+            Chain::Sepolia => "https://api-sepolia.etherscan.io/api",
+            Chain::Holesky => "https://api-holesky.etherscan.io/api",
+        }
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chain::Mainnet => write!(f, "mainnet"),
+            Chain::Goerli => write!(f, "goerli"),
+            Chain::Sepolia => write!(f, "sepolia"),
+            Chain::Holesky => write!(f, "holesky"),
+        }
+    }
+}
+
+impl FromStr for Chain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Chain::Mainnet),
+            "goerli" => Ok(Chain::Goerli),
+            "sepolia" => Ok(Chain::Sepolia),
+            "holesky" => Ok(Chain::Holesky),
+            _ => Err(format!("unknown chain '{}'", s)),
+        }
+    }
+}
+
+pub enum ClientType {
+    Geth,
+    Parity,
+}
+
+impl fmt::Display for ClientType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientType::Geth => write!(f, "geth"),
+            ClientType::Parity => write!(f, "parity"),
+        }
+    }
+}
+
+pub enum SyncMode {
+    Default,
+    Archive,
+}
+
+impl fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncMode::Default => write!(f, "default"),
+            SyncMode::Archive => write!(f, "archive"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NodeCount {
+    #[serde(rename(deserialize = "UTCDate"))]
+    pub utc_date: NaiveDate,
+    #[serde(deserialize_with = "from_str", rename(deserialize = "TotalNodeCount"))]
+    pub total_node_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyTxCount {
+    #[serde(deserialize_with = "from_str", rename(deserialize = "transactionCount"))]
+    pub transaction_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyNewAddressCount {
+    #[serde(deserialize_with = "from_str", rename(deserialize = "newAddressCount"))]
+    pub new_address_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyNetworkUtilization {
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "networkUtilization"))]
+    pub network_utilization: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyTxFee {
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "transactionFee_Eth"))]
+    pub transaction_fee_eth: f64,
+}
+
+/// The average hash rate in GH/s.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyHashRate {
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "networkHashRate"))]
+    pub network_hash_rate: f64,
+}
+
+/// The average difficulty in TH.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyDifficulty {
+    #[serde(deserialize_with = "from_grouped_str", rename(deserialize = "networkDifficulty"))]
+    pub network_difficulty: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyBlockSize {
+    #[serde(deserialize_with = "from_str", rename(deserialize = "blockSize_bytes"))]
+    pub block_size_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyBlockCount {
+    #[serde(deserialize_with = "from_str", rename(deserialize = "blockCount"))]
+    pub block_count: u64,
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "blockRewards_Eth"))]
+    pub block_rewards_eth: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyBlockTime {
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "blockTime_sec"))]
+    pub block_time_sec: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyUncleBlockCount {
+    #[serde(deserialize_with = "from_str", rename(deserialize = "uncleBlockCount"))]
+    pub uncle_block_count: u64,
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "uncleBlockRewards_Eth"))]
+    pub uncle_block_rewards_eth: f64,
+}
+
+/// The ether supply, market cap and price in USD of a given day.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DailyMarketCap {
+    #[serde(deserialize_with = "from_decimal_str")]
+    pub supply: f64,
+    #[serde(deserialize_with = "from_decimal_str", rename(deserialize = "marketCap"))]
+    pub market_cap: f64,
+    #[serde(deserialize_with = "from_decimal_str")]
+    pub price: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_contract_creation() {
+        let tx: Transaction = serde_json::from_str(r#"{"blockNumber":"14923678","timeStamp":"1654646411","hash":"0xc52783ad354aecc04c670047754f062e3d6d04e8f5b24774472651f9c3882c60","nonce":"1","blockHash":"0x7e1638fd2c6bdd05ffd83c1cf06c63e2f67d0f802084bef076d06bdcf86d1bb0","transactionIndex":"61","from":"0x9aa99c23f67c81701c772b106b4f83f6e858dd2e","to":"","value":"0","gas":"6000000","gasPrice":"83924748773","isError":"0","txreceipt_status":"1","input":"0x","contractAddress":"0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc","cumulativeGasUsed":"4457269","gasUsed":"4457269","confirmations":"122485","methodId":"0x61016060","functionName":""}"#).unwrap();
+        assert_eq!(tx.to(), None);
+        assert_eq!(tx.contract_address(), Some("0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc"));
+        assert_eq!(tx.receipt_status(), Some(&ReceiptStatus::Pass));
+        assert_eq!(tx.fee(), Some(U256::from(4457269u64) * U256::from(83924748773u64)));
+    }
+
+    #[test]
+    fn decode_empty_gas_used() {
+        let tx: Transaction = serde_json::from_str(r#"{"blockNumber":"14923678","timeStamp":"1654646411","hash":"0xc52783ad354aecc04c670047754f062e3d6d04e8f5b24774472651f9c3882c60","nonce":"1","blockHash":"0x7e1638fd2c6bdd05ffd83c1cf06c63e2f67d0f802084bef076d06bdcf86d1bb0","transactionIndex":"61","from":"0x9aa99c23f67c81701c772b106b4f83f6e858dd2e","to":"","value":"0","gas":"6000000","gasPrice":"83924748773","isError":"0","txreceipt_status":"1","input":"0x","contractAddress":"0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc","cumulativeGasUsed":"4457269","gasUsed":"","confirmations":"122485"}"#).unwrap();
+        assert_eq!(tx.gas_used(), None);
+        assert_eq!(tx.fee(), None);
+
+        let transfer: ERC20TokenTransferEvent = serde_json::from_str(r#"{"blockNumber":"14923678","timeStamp":"1654646411","hash":"0xc52783ad354aecc04c670047754f062e3d6d04e8f5b24774472651f9c3882c60","nonce":"1","blockHash":"0x7e1638fd2c6bdd05ffd83c1cf06c63e2f67d0f802084bef076d06bdcf86d1bb0","from":"0x9aa99c23f67c81701c772b106b4f83f6e858dd2e","contractAddress":"0x514910771af9ca656af840dff83e8264ecf986ca","to":"0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc","value":"1","tokenName":"ChainLink Token","tokenSymbol":"LINK","tokenDecimal":"18","transactionIndex":"61","gas":"6000000","gasPrice":"83924748773","gasUsed":"","cumulativeGasUsed":"4457269","input":"deprecated","confirmations":"122485"}"#).unwrap();
+        assert_eq!(transfer.gas_used(), None);
+        assert_eq!(transfer.fee(), None);
+    }
+
+    #[test]
+    fn decode_pre_byzantium_and_internal() {
+        let tx: Transaction = serde_json::from_str(r#"{"blockNumber":"54092","timeStamp":"1439048640","hash":"0x9c81f44c29ff0226f835cd0a8a2f2a7eca6db52a711f8211b566fd15d3e0e8d4","nonce":"0","blockHash":"0xd3cabad6adab0b52eb632c386ea194036805713682c62cb589b5abcd76de2159","transactionIndex":"0","from":"0x5abfec25f74cd88437631a7731906932776356f9","to":"","value":"11901464239480000000000000","gas":"2000000","gasPrice":"10000000000000","isError":"0","txreceipt_status":"","input":"0x","contractAddress":"0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae","cumulativeGasUsed":"1436963","gasUsed":"1436963","confirmations":"14869717"}"#).unwrap();
+        assert_eq!(tx.receipt_status(), None);
+
+        let internal: InternalTransaction = serde_json::from_str(r#"{"blockNumber":"2535479","timeStamp":"1477837690","hash":"0x8a1a9989bda84f80143181a68bc137ecefa64d0d4ebde45dd94fc0cf49e70cb6","from":"0x20d42f2e99a421147acf198d775395cac2e8b03d","to":"","value":"0","contractAddress":"0x2c1ba59d6f58433fb1eaee7d20b26ed83bda51a3","input":"","type":"create","gas":"254791","gasUsed":"","traceId":"0","isError":"1","errCode":"Out of gas"}"#).unwrap();
+        assert_eq!(internal.to(), None);
+        assert_eq!(internal.gas_used(), None);
+        assert!(internal.is_error());
+        assert_eq!(internal.err_code(), Some("Out of gas"));
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::{delay_until, Instant};
+
+/// Spaces requests evenly so that no more than the given number are sent per second.
+/// Etherscan allows 5 requests per second on the free plan.
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u32) -> RateLimiter {
+        RateLimiter {
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// The time between two requests.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Waits until the next request may be sent.
+    pub async fn acquire(&self) {
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        if *next > now {
+            delay_until(*next).await;
+        }
+        *next = (*next).max(now) + self.interval;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn spaces_requests() {
+        let limiter = RateLimiter::new(50);
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        // the first request goes out right away
+        assert!(start.elapsed() >= limiter.interval() * 4);
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::NaiveDate;
+use reqwest::Url;
+
+use super::AsyncError;
+
+/// A call of an Etherscan action. The API key is not part of it, it is only added when the request
+/// is sent, so a request can be logged, compared and used as cache key.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Request {
+    module: String,
+    action: String,
+    params: Vec<(String, String)>,
+}
+
+impl Request {
+    pub(crate) fn new(module: &str, action: &str) -> Request {
+        Request { module: module.into(), action: action.into(), params: vec![] }
+    }
+
+    pub(crate) fn param<V: ToString>(mut self, name: &str, value: V) -> Request {
+        self.params.push((name.into(), value.to_string()));
+        self
+    }
+
+    pub(crate) fn optional_param<V: ToString>(self, name: &str, value: Option<V>) -> Request {
+        match value {
+            Some(value) => self.param(name, value),
+            None => self,
+        }
+    }
+
+    /// Restricts the request to the block range, unless `end_block` is 0.
+    pub(crate) fn block_range(self, from_block: u64, end_block: u64) -> Request {
+        if end_block == 0 {
+            return self;
+        }
+        self.param("startblock", from_block).param("endblock", end_block)
+    }
+
+    pub(crate) fn date_range(self, start: NaiveDate, end: NaiveDate) -> Request {
+        self.param("startdate", start.format("%Y-%m-%d")).param("enddate", end.format("%Y-%m-%d"))
+    }
+
+    pub(crate) fn module(&self) -> &str {
+        &self.module
+    }
+
+    pub(crate) fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// The parameters besides module and action.
+    pub(crate) fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    /// The query parameters, starting with module and action.
+    pub(crate) fn query(&self) -> Vec<(&str, &str)> {
+        let mut query = vec![("module", self.module.as_str()), ("action", self.action.as_str())];
+        query.extend(self.params.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+        query
+    }
+
+    /// The URL of the request to the endpoint, without API key.
+    pub(crate) fn url(&self, base_url: &str) -> Result<String, AsyncError> {
+        Ok(Url::parse_with_params(base_url, self.query())?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_params() {
+        let request = Request::new("account", "txlist").param("address", "0x1 &x=y").block_range(0, 0).block_range(1, 2);
+        assert_eq!(request.url("https://api.etherscan.io/api").unwrap(),
+                   "https://api.etherscan.io/api?module=account&action=txlist&address=0x1+%26x%3Dy&startblock=1&endblock=2");
+        assert!(request.url("api.etherscan.io/api").is_err());
+    }
+}
@@ -0,0 +1,466 @@
+//! SQLite storage for the list models and the stat series, behind the `sqlite` feature.
+//!
+//! Transactions, internal transactions, ERC20 and ERC721 transfers, logs, mined blocks, chain sizes
+//! and each of the daily stat series get a table whose columns carry Etherscan's field names. The
+//! daily series are keyed by their UTC date. Current values such as the ether price, the supply,
+//! the node count or the gas oracle are snapshots rather than series and aren't stored. Values are
+//! stored as Etherscan returns them, as text, except for `blockNumber`, `timeStamp` and
+//! `unixTimeStamp`, which are integers so that ranges can be queried. Rows are read back through the
+//! models' deserializers.
+
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::types::ValueRef;
+use rusqlite::{params_from_iter, Connection, Row};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use super::export::EtherscanFields;
+use super::models::{
+    ChainSize, DailyBlockCount, DailyBlockSize, DailyBlockTime, DailyDifficulty, DailyGasLimit, DailyGasPrice, DailyGasUsed,
+    DailyHashRate, DailyMarketCap, DailyNetworkUtilization, DailyNewAddressCount, DailyPrice, DailyStat, DailyTxCount,
+    DailyTxFee, DailyUncleBlockCount, ERC20TokenTransferEvent, ERC721TokenTransferEvent, InternalTransaction, Log,
+    MinedBlock, Transaction,
+};
+use super::AsyncError;
+
+const TRANSACTIONS: &str = "transactions";
+const INTERNAL_TRANSACTIONS: &str = "internal_transactions";
+const ERC20_TRANSFERS: &str = "erc20_transfers";
+const ERC721_TRANSFERS: &str = "erc721_transfers";
+const LOGS: &str = "logs";
+const MINED_BLOCKS: &str = "mined_blocks";
+const CHAIN_SIZES: &str = "chain_sizes";
+
+// the columns every daily series starts with
+const DAILY_FIELDS: &[&str] = &["UTCDate", "unixTimeStamp"];
+
+// transfers are keyed by tx hash and log index, the content only tells them apart where Etherscan
+// doesn't send the log index
+const TRANSFER_KEY: &[&str] = &["hash", "logIndex", "contractAddress", "from", "to", "value"];
+
+/// A daily stat series, stored in a table of its own.
+pub trait DailySeries: DeserializeOwned {
+    const TABLE: &'static str;
+    /// The series' value fields as Etherscan names them, following `UTCDate` and `unixTimeStamp`.
+    const FIELDS: &'static [&'static str];
+
+    fn field_values(&self) -> Vec<String>;
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens the database, creating the tables which don't exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Store, AsyncError> {
+        Store::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Store, AsyncError> {
+        Store::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Store, AsyncError> {
+        let store = Store { conn };
+        store.create_table(TRANSACTIONS, Transaction::FIELDS, &["hash"])?;
+        store.create_table(INTERNAL_TRANSACTIONS, InternalTransaction::FIELDS, &["hash", "traceId"])?;
+        store.create_table(ERC20_TRANSFERS, ERC20TokenTransferEvent::FIELDS, TRANSFER_KEY)?;
+        store.create_table(ERC721_TRANSFERS, ERC721TokenTransferEvent::FIELDS, TRANSFER_KEY)?;
+        store.create_table(LOGS, Log::FIELDS, &["transactionHash", "logIndex"])?;
+        store.create_table(MINED_BLOCKS, MinedBlock::FIELDS, &["blockNumber"])?;
+        store.create_table(CHAIN_SIZES, ChainSize::FIELDS, &["chainTimeStamp", "clientType", "syncMode"])?;
+        store.create_daily_table::<DailyGasLimit>()?;
+        store.create_daily_table::<DailyGasUsed>()?;
+        store.create_daily_table::<DailyGasPrice>()?;
+        store.create_daily_table::<DailyPrice>()?;
+        store.create_daily_table::<DailyTxCount>()?;
+        store.create_daily_table::<DailyNewAddressCount>()?;
+        store.create_daily_table::<DailyNetworkUtilization>()?;
+        store.create_daily_table::<DailyTxFee>()?;
+        store.create_daily_table::<DailyHashRate>()?;
+        store.create_daily_table::<DailyDifficulty>()?;
+        store.create_daily_table::<DailyBlockSize>()?;
+        store.create_daily_table::<DailyBlockCount>()?;
+        store.create_daily_table::<DailyBlockTime>()?;
+        store.create_daily_table::<DailyUncleBlockCount>()?;
+        store.create_daily_table::<DailyMarketCap>()?;
+        Ok(store)
+    }
+
+    fn create_table(&self, table: &str, fields: &[&str], key: &[&str]) -> Result<(), AsyncError> {
+        let columns: Vec<String> = fields.iter().map(|field| match *field {
+            "blockNumber" | "timeStamp" | "unixTimeStamp" => format!("\"{}\" INTEGER NOT NULL", field),
+            _ => format!("\"{}\" TEXT NOT NULL", field),
+        }).collect();
+        let key: Vec<String> = key.iter().map(|k| format!("\"{}\"", k)).collect();
+        self.conn.execute_batch(&format!("CREATE TABLE IF NOT EXISTS {} ({}, PRIMARY KEY ({}))", table, columns.join(", "), key.join(", ")))?;
+        if fields.contains(&"blockNumber") {
+            self.conn.execute_batch(&format!("CREATE INDEX IF NOT EXISTS {table}_block ON {table} (\"blockNumber\")", table = table))?;
+        }
+        Ok(())
+    }
+
+    fn create_daily_table<T: DailySeries>(&self) -> Result<(), AsyncError> {
+        self.create_table(T::TABLE, &[DAILY_FIELDS, T::FIELDS].concat(), &["UTCDate"])
+    }
+
+    /// Inserts the rows, replacing those with the same key. Returns the number of rows written.
+    fn insert(&mut self, table: &str, fields: &[&str], rows: impl Iterator<Item = Vec<String>>) -> Result<usize, AsyncError> {
+        let columns: Vec<String> = fields.iter().map(|field| format!("\"{}\"", field)).collect();
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let tx = self.conn.transaction()?;
+        let mut written = 0;
+        {
+            let mut statement = tx.prepare(&format!("INSERT OR REPLACE INTO {} ({}) VALUES ({})", table, columns.join(", "), placeholders))?;
+            for row in rows {
+                statement.execute(params_from_iter(row))?;
+                written += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(written)
+    }
+
+    fn upsert<R: EtherscanFields>(&mut self, table: &str, records: &[R]) -> Result<usize, AsyncError> {
+        self.insert(table, R::FIELDS, records.iter().map(|record| record.field_values()))
+    }
+
+    pub fn upsert_transactions(&mut self, txs: &[Transaction]) -> Result<usize, AsyncError> {
+        self.upsert(TRANSACTIONS, txs)
+    }
+
+    pub fn upsert_internal_transactions(&mut self, txs: &[InternalTransaction]) -> Result<usize, AsyncError> {
+        self.upsert(INTERNAL_TRANSACTIONS, txs)
+    }
+
+    pub fn upsert_erc20_transfers(&mut self, transfers: &[ERC20TokenTransferEvent]) -> Result<usize, AsyncError> {
+        self.upsert(ERC20_TRANSFERS, transfers)
+    }
+
+    pub fn upsert_erc721_transfers(&mut self, transfers: &[ERC721TokenTransferEvent]) -> Result<usize, AsyncError> {
+        self.upsert(ERC721_TRANSFERS, transfers)
+    }
+
+    pub fn upsert_logs(&mut self, logs: &[Log]) -> Result<usize, AsyncError> {
+        self.upsert(LOGS, logs)
+    }
+
+    pub fn upsert_mined_blocks(&mut self, blocks: &[MinedBlock]) -> Result<usize, AsyncError> {
+        self.upsert(MINED_BLOCKS, blocks)
+    }
+
+    pub fn upsert_chain_sizes(&mut self, sizes: &[ChainSize]) -> Result<usize, AsyncError> {
+        self.upsert(CHAIN_SIZES, sizes)
+    }
+
+    /// Stores the days of a daily stat series, replacing days stored before.
+    pub fn upsert_daily_stats<T: DailySeries>(&mut self, stats: &[DailyStat<T>]) -> Result<usize, AsyncError> {
+        let rows = stats.iter().map(|stat| {
+            let mut values = vec![stat.utc_date().to_string(), stat.timestamp().to_string()];
+            values.extend(stat.value().field_values());
+            values
+        });
+        self.insert(T::TABLE, &[DAILY_FIELDS, T::FIELDS].concat(), rows)
+    }
+
+    /// Runs a query selecting the fields from the table and reads the rows back into records.
+    fn select<R: DeserializeOwned>(&self, table: &str, fields: &[&str], condition: &str, order: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<R>, AsyncError> {
+        let columns: Vec<String> = fields.iter().map(|field| format!("\"{}\"", field)).collect();
+        let sql = format!("SELECT {} FROM {} WHERE {} ORDER BY {}", columns.join(", "), table, condition, order);
+        let mut statement = self.conn.prepare(&sql)?;
+        let mut rows = statement.query(params)?;
+        let mut records = vec![];
+        while let Some(row) = rows.next()? {
+            records.push(serde_json::from_value(row_to_json(fields, row)?)?);
+        }
+        Ok(records)
+    }
+
+    fn query<R: EtherscanFields + DeserializeOwned>(&self, table: &str, condition: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<R>, AsyncError> {
+        self.select(table, R::FIELDS, condition, "\"blockNumber\", rowid", params)
+    }
+
+    /// The account's transactions, sent or received, within the block range.
+    pub fn transactions(&self, address: &str, from_block: u64, to_block: u64) -> Result<Vec<Transaction>, AsyncError> {
+        self.query(TRANSACTIONS, "(lower(\"from\") = lower(?1) OR lower(\"to\") = lower(?1)) AND \"blockNumber\" BETWEEN ?2 AND ?3",
+                   &[&address, &(from_block as i64), &(to_block as i64)])
+    }
+
+    /// The internal transactions of a transaction.
+    pub fn internal_transactions(&self, tx_hash: &str) -> Result<Vec<InternalTransaction>, AsyncError> {
+        self.query(INTERNAL_TRANSACTIONS, "lower(\"hash\") = lower(?1)", &[&tx_hash])
+    }
+
+    /// All transfers of the token from or to the account between the two dates, inclusive.
+    pub fn erc20_transfers(&self, address: &str, token_contract_addr: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<ERC20TokenTransferEvent>, AsyncError> {
+        self.query(ERC20_TRANSFERS,
+                   "(lower(\"from\") = lower(?1) OR lower(\"to\") = lower(?1)) AND lower(\"contractAddress\") = lower(?2) AND \"timeStamp\" BETWEEN ?3 AND ?4",
+                   &[&address, &token_contract_addr, &from.timestamp(), &to.timestamp()])
+    }
+
+    /// All ERC721 transfers of the collection from or to the account between the two dates, inclusive.
+    pub fn erc721_transfers(&self, address: &str, token_contract_addr: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<ERC721TokenTransferEvent>, AsyncError> {
+        self.query(ERC721_TRANSFERS,
+                   "(lower(\"from\") = lower(?1) OR lower(\"to\") = lower(?1)) AND lower(\"contractAddress\") = lower(?2) AND \"timeStamp\" BETWEEN ?3 AND ?4",
+                   &[&address, &token_contract_addr, &from.timestamp(), &to.timestamp()])
+    }
+
+    /// The logs emitted by the contract within the block range.
+    pub fn logs(&self, address: &str, from_block: u64, to_block: u64) -> Result<Vec<Log>, AsyncError> {
+        self.query(LOGS, "lower(\"address\") = lower(?1) AND \"blockNumber\" BETWEEN ?2 AND ?3",
+                   &[&address, &(from_block as i64), &(to_block as i64)])
+    }
+
+    pub fn mined_blocks(&self, from_block: u64, to_block: u64) -> Result<Vec<MinedBlock>, AsyncError> {
+        self.query(MINED_BLOCKS, "\"blockNumber\" BETWEEN ?1 AND ?2", &[&(from_block as i64), &(to_block as i64)])
+    }
+
+    /// The chain sizes of all client types and sync modes between the two dates, inclusive.
+    pub fn chain_sizes(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<ChainSize>, AsyncError> {
+        self.query(CHAIN_SIZES, "\"chainTimeStamp\" BETWEEN ?1 AND ?2", &[&start_date.to_string(), &end_date.to_string()])
+    }
+
+    /// The stored days of a daily stat series between the two dates, inclusive.
+    pub fn daily_stats<T: DailySeries>(&self, start_date: NaiveDate, end_date: NaiveDate) -> Result<Vec<DailyStat<T>>, AsyncError> {
+        self.select(T::TABLE, &[DAILY_FIELDS, T::FIELDS].concat(), "\"UTCDate\" BETWEEN ?1 AND ?2", "\"UTCDate\"",
+                    &[&start_date.to_string(), &end_date.to_string()])
+    }
+}
+
+impl DailySeries for DailyGasLimit {
+    const TABLE: &'static str = "daily_gas_limits";
+    const FIELDS: &'static [&'static str] = &["gasLimit"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.gas_limit.to_string()]
+    }
+}
+
+impl DailySeries for DailyGasUsed {
+    const TABLE: &'static str = "daily_gas_used";
+    const FIELDS: &'static [&'static str] = &["gasUsed"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.gas_used.to_string()]
+    }
+}
+
+impl DailySeries for DailyGasPrice {
+    const TABLE: &'static str = "daily_gas_prices";
+    const FIELDS: &'static [&'static str] = &["maxGasPrice_Wei", "minGasPrice_Wei", "avgGasPrice_Wei"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.max_gas_price.to_string(), self.min_gas_price.to_string(), self.avg_gas_price.to_string()]
+    }
+}
+
+impl DailySeries for DailyPrice {
+    const TABLE: &'static str = "daily_prices";
+    const FIELDS: &'static [&'static str] = &["value"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.price.to_string()]
+    }
+}
+
+impl DailySeries for DailyTxCount {
+    const TABLE: &'static str = "daily_tx_counts";
+    const FIELDS: &'static [&'static str] = &["transactionCount"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.transaction_count.to_string()]
+    }
+}
+
+impl DailySeries for DailyNewAddressCount {
+    const TABLE: &'static str = "daily_new_address_counts";
+    const FIELDS: &'static [&'static str] = &["newAddressCount"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.new_address_count.to_string()]
+    }
+}
+
+impl DailySeries for DailyNetworkUtilization {
+    const TABLE: &'static str = "daily_network_utilization";
+    const FIELDS: &'static [&'static str] = &["networkUtilization"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.network_utilization.to_string()]
+    }
+}
+
+impl DailySeries for DailyTxFee {
+    const TABLE: &'static str = "daily_tx_fees";
+    const FIELDS: &'static [&'static str] = &["transactionFee_Eth"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.transaction_fee_eth.to_string()]
+    }
+}
+
+impl DailySeries for DailyHashRate {
+    const TABLE: &'static str = "daily_hash_rates";
+    const FIELDS: &'static [&'static str] = &["networkHashRate"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.network_hash_rate.to_string()]
+    }
+}
+
+impl DailySeries for DailyDifficulty {
+    const TABLE: &'static str = "daily_difficulties";
+    const FIELDS: &'static [&'static str] = &["networkDifficulty"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.network_difficulty.to_string()]
+    }
+}
+
+impl DailySeries for DailyBlockSize {
+    const TABLE: &'static str = "daily_block_sizes";
+    const FIELDS: &'static [&'static str] = &["blockSize_bytes"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.block_size_bytes.to_string()]
+    }
+}
+
+impl DailySeries for DailyBlockCount {
+    const TABLE: &'static str = "daily_block_counts";
+    const FIELDS: &'static [&'static str] = &["blockCount", "blockRewards_Eth"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.block_count.to_string(), self.block_rewards_eth.to_string()]
+    }
+}
+
+impl DailySeries for DailyBlockTime {
+    const TABLE: &'static str = "daily_block_times";
+    const FIELDS: &'static [&'static str] = &["blockTime_sec"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.block_time_sec.to_string()]
+    }
+}
+
+impl DailySeries for DailyUncleBlockCount {
+    const TABLE: &'static str = "daily_uncle_block_counts";
+    const FIELDS: &'static [&'static str] = &["uncleBlockCount", "uncleBlockRewards_Eth"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.uncle_block_count.to_string(), self.uncle_block_rewards_eth.to_string()]
+    }
+}
+
+impl DailySeries for DailyMarketCap {
+    const TABLE: &'static str = "daily_market_caps";
+    const FIELDS: &'static [&'static str] = &["supply", "marketCap", "price"];
+
+    fn field_values(&self) -> Vec<String> {
+        vec![self.supply.to_string(), self.market_cap.to_string(), self.price.to_string()]
+    }
+}
+
+/// Rebuilds the record as Etherscan would have returned it.
+fn row_to_json(fields: &[&str], row: &Row) -> Result<Value, AsyncError> {
+    let mut object = Map::new();
+    for (i, field) in fields.iter().enumerate() {
+        let text = match row.get_ref(i)? {
+            ValueRef::Integer(n) => n.to_string(),
+            ValueRef::Text(text) => String::from_utf8(text.to_vec())?,
+            _ => String::new(),
+        };
+        let value = match *field {
+            "topics" => serde_json::from_str(&text)?,
+            _ => Value::String(text),
+        };
+        object.insert(field.to_string(), value);
+    }
+    Ok(Value::Object(object))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const ADDR: &str = "0xddbd2b932c763ba5b1b7ae3b362eac3e8d40121a";
+    const CHAIN_LINK: &str = "0x514910771af9ca656af840dff83e8264ecf986ca";
+
+    fn fixture<R: DeserializeOwned>(name: &str) -> Vec<R> {
+        let path = format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let fixture: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        serde_json::from_value(fixture["response"]["result"].clone()).unwrap()
+    }
+
+    #[test]
+    fn upserts_and_queries() {
+        let mut store = Store::open_in_memory().unwrap();
+        let txs: Vec<Transaction> = fixture("account_txlist_551a1d2b.json");
+        assert_eq!(store.upsert_transactions(&txs).unwrap(), 2);
+        // writing the same records again replaces them
+        store.upsert_transactions(&txs).unwrap();
+        let stored = store.transactions(&ADDR.to_uppercase().replace("0X", "0x"), 0, 20_000_000).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].to(), None);
+        assert_eq!(stored[1].fee(), txs[1].fee());
+        assert_eq!(stored[1].receipt_status(), txs[1].receipt_status());
+
+        let transfers: Vec<ERC20TokenTransferEvent> = fixture("account_tokentx_74ba0314.json");
+        store.upsert_erc20_transfers(&transfers).unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2022, 6, d, 0, 0, 0).unwrap();
+        let link = store.erc20_transfers(ADDR, CHAIN_LINK, day(7), day(9)).unwrap();
+        assert_eq!(link.len(), 1);
+        assert_eq!(link[0].amount().to_string(), "1 LINK");
+        assert!(store.erc20_transfers(ADDR, CHAIN_LINK, day(9), day(10)).unwrap().is_empty());
+
+        let internal: Vec<InternalTransaction> = fixture("account_txlistinternal_dab16ead.json");
+        store.upsert_internal_transactions(&internal).unwrap();
+        assert_eq!(store.internal_transactions(txs[1].hash()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn keeps_identical_transfers_of_a_tx_apart() {
+        let mut store = Store::open_in_memory().unwrap();
+        let path = format!("{}/fixtures/account_tokentx_74ba0314.json", env!("CARGO_MANIFEST_DIR"));
+        let fixture: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let transfers: Vec<ERC20TokenTransferEvent> = (1..=2).map(|log_index| {
+            let mut transfer = fixture["response"]["result"][0].clone();
+            transfer["logIndex"] = Value::String(log_index.to_string());
+            serde_json::from_value(transfer).unwrap()
+        }).collect();
+        store.upsert_erc20_transfers(&transfers).unwrap();
+        store.upsert_erc20_transfers(&transfers).unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2022, 6, d, 0, 0, 0).unwrap();
+        let stored = store.erc20_transfers(ADDR, CHAIN_LINK, day(7), day(9)).unwrap();
+        assert_eq!(stored.iter().map(|t| t.log_index()).collect::<Vec<_>>(), vec![Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn stores_stat_series_by_date() {
+        let mut store = Store::open_in_memory().unwrap();
+        let prices: Vec<DailyStat<DailyGasPrice>> = fixture("stats_dailyavggasprice_399d6f23.json");
+        assert_eq!(store.upsert_daily_stats(&prices).unwrap(), 2);
+        store.upsert_daily_stats(&prices).unwrap();
+        let date = |d| NaiveDate::from_ymd_opt(2019, 2, d).unwrap();
+        let stored: Vec<DailyStat<DailyGasPrice>> = store.daily_stats(date(1), date(28)).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[1].utc_date(), date(28));
+        assert_eq!(stored[1].timestamp(), 1551312000);
+        assert_eq!(stored[1].value().max_gas_price, 200000000000000);
+        assert_eq!(store.daily_stats::<DailyGasPrice>(date(2), date(27)).unwrap().len(), 0);
+        // each series has a table of its own
+        assert!(store.daily_stats::<DailyGasUsed>(date(1), date(28)).unwrap().is_empty());
+
+        let sizes: Vec<ChainSize> = fixture("stats_chainsize_000f62d4.json");
+        store.upsert_chain_sizes(&sizes).unwrap();
+        let stored = store.chain_sizes(date(2), date(28)).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].chain_size, 184981693634);
+    }
+}
@@ -0,0 +1,331 @@
+//! Checkpoints for long-running indexers, so that paging through an account's lists resumes where
+//! it left off after a restart.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::models::{ERC20TokenTransferEvent, ERC721TokenTransferEvent, InternalTransaction, Transaction};
+use super::{AsyncError, API, LATEST_BLOCK};
+
+/// The lists which can be paged through.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordKind {
+    Transactions,
+    InternalTransactions,
+    ERC20Transfers,
+    ERC721Transfers,
+}
+
+impl RecordKind {
+    /// The Etherscan action fetching the list.
+    pub fn action(&self) -> &'static str {
+        match self {
+            RecordKind::Transactions => "txlist",
+            RecordKind::InternalTransactions => "txlistinternal",
+            RecordKind::ERC20Transfers => "tokentx",
+            RecordKind::ERC721Transfers => "tokennfttx",
+        }
+    }
+}
+
+impl fmt::Display for RecordKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.action())
+    }
+}
+
+/// Where paging through a list stands: the query starting at `block` is read up to record `index`
+/// of page `page`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Cursor {
+    pub block: u64,
+    /// Counted from 1, as Etherscan does.
+    pub page: u64,
+    /// The number of records of the page which are processed.
+    pub index: u64,
+}
+
+impl Default for Cursor {
+    fn default() -> Cursor {
+        Cursor { block: 0, page: 1, index: 0 }
+    }
+}
+
+/// Stores a cursor per address and kind of list.
+pub trait SyncState: Send + Sync {
+    fn get(&self, address: &str, kind: RecordKind) -> Option<Cursor>;
+
+    fn set(&self, address: &str, kind: RecordKind, cursor: Cursor) -> io::Result<()>;
+}
+
+fn state_key(address: &str, kind: RecordKind) -> String {
+    format!("{}/{}", address.to_lowercase(), kind)
+}
+
+#[derive(Default)]
+pub struct MemorySyncState {
+    cursors: Mutex<BTreeMap<String, Cursor>>,
+}
+
+impl MemorySyncState {
+    pub fn new() -> MemorySyncState {
+        MemorySyncState::default()
+    }
+}
+
+impl SyncState for MemorySyncState {
+    fn get(&self, address: &str, kind: RecordKind) -> Option<Cursor> {
+        self.cursors.lock().unwrap().get(&state_key(address, kind)).copied()
+    }
+
+    fn set(&self, address: &str, kind: RecordKind, cursor: Cursor) -> io::Result<()> {
+        self.cursors.lock().unwrap().insert(state_key(address, kind), cursor);
+        Ok(())
+    }
+}
+
+/// Keeps all cursors in one JSON file, which is replaced atomically on every change.
+pub struct FileSyncState {
+    path: PathBuf,
+    cursors: Mutex<BTreeMap<String, Cursor>>,
+}
+
+impl FileSyncState {
+    /// Loads the cursors from the file, if it exists.
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<FileSyncState> {
+        let path = path.into();
+        let cursors = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(FileSyncState { path, cursors: Mutex::new(cursors) })
+    }
+}
+
+impl SyncState for FileSyncState {
+    fn get(&self, address: &str, kind: RecordKind) -> Option<Cursor> {
+        self.cursors.lock().unwrap().get(&state_key(address, kind)).copied()
+    }
+
+    fn set(&self, address: &str, kind: RecordKind, cursor: Cursor) -> io::Result<()> {
+        let mut cursors = self.cursors.lock().unwrap();
+        cursors.insert(state_key(address, kind), cursor);
+        let content = serde_json::to_string_pretty(&*cursors)?;
+        // a crash while writing must not lose the previous state
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+/// A record of a list which can be paged through.
+pub trait PagedRecord: DeserializeOwned + fmt::Debug + Send + Sync + 'static {
+    /// The list the records come from.
+    const KIND: RecordKind;
+
+    fn block_number(&self) -> u64;
+}
+
+impl PagedRecord for Transaction {
+    const KIND: RecordKind = RecordKind::Transactions;
+
+    fn block_number(&self) -> u64 {
+        self.block_number()
+    }
+}
+
+impl PagedRecord for InternalTransaction {
+    const KIND: RecordKind = RecordKind::InternalTransactions;
+
+    fn block_number(&self) -> u64 {
+        self.block_number()
+    }
+}
+
+impl PagedRecord for ERC20TokenTransferEvent {
+    const KIND: RecordKind = RecordKind::ERC20Transfers;
+
+    fn block_number(&self) -> u64 {
+        self.block_number()
+    }
+}
+
+impl PagedRecord for ERC721TokenTransferEvent {
+    const KIND: RecordKind = RecordKind::ERC721Transfers;
+
+    fn block_number(&self) -> u64 {
+        ERC20TokenTransferEvent::block_number(self)
+    }
+}
+
+/// Pages through an account's list from its stored cursor on. A page counts as processed once it
+/// is committed, so a crash before the commit hands out the same page again after the restart.
+///
+/// ```ignore
+/// let mut txs = ResumableFetcher::<Transaction>::new(&api, &state, addr);
+/// loop {
+///     let page = txs.next_page().await?;
+///     if page.is_empty() { break; }
+///     store(page);
+///     txs.commit()?;
+/// }
+/// ```
+pub struct ResumableFetcher<'a, R> {
+    api: &'a API,
+    state: &'a dyn SyncState,
+    address: String,
+    page_size: u64,
+    // the cursor past the page handed out last
+    pending: Option<Cursor>,
+    records: PhantomData<R>,
+}
+
+impl<'a, R: PagedRecord> ResumableFetcher<'a, R> {
+    pub fn new(api: &'a API, state: &'a dyn SyncState, address: &str) -> ResumableFetcher<'a, R> {
+        ResumableFetcher { api, state, address: address.into(), page_size: 1000, pending: None, records: PhantomData }
+    }
+
+    /// 1000 records by default. Must not change between runs using the same state.
+    pub fn with_page_size(mut self, page_size: u64) -> ResumableFetcher<'a, R> {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// The committed cursor.
+    pub fn cursor(&self) -> Cursor {
+        self.state.get(&self.address, R::KIND).unwrap_or_default()
+    }
+
+    /// The records after the committed cursor, empty once the list is read to its end.
+    pub async fn next_page(&mut self) -> Result<Vec<R>, AsyncError> {
+        let cursor = self.cursor();
+        let records: Vec<R> = self.api.fetch_account_page(R::KIND.action(), &self.address, cursor.block, LATEST_BLOCK, cursor.page, self.page_size).await?;
+        let count = records.len() as u64;
+        self.pending = Some(next_cursor(cursor, &records, self.page_size));
+        Ok(records.into_iter().skip(cursor.index.min(count) as usize).collect())
+    }
+
+    /// Marks the page handed out last as processed.
+    pub fn commit(&mut self) -> io::Result<()> {
+        match self.pending.take() {
+            Some(cursor) => self.state.set(&self.address, R::KIND, cursor),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Fetches all records of the account's list within the block range, page by page, so that
+/// Etherscan's limit of 10000 records per query doesn't cut the list short.
+pub(crate) async fn fetch_range<R: PagedRecord>(api: &API, address: &str, from_block: u64, end_block: u64, page_size: u64) -> Result<Vec<R>, AsyncError> {
+    let mut cursor = Cursor { block: from_block, page: 1, index: 0 };
+    let mut all = vec![];
+    loop {
+        let records: Vec<R> = api.fetch_account_page(R::KIND.action(), address, cursor.block, end_block, cursor.page, page_size).await?;
+        let count = records.len() as u64;
+        let next = next_cursor(cursor, &records, page_size);
+        all.extend(records.into_iter().skip(cursor.index.min(count) as usize));
+        if count < page_size {
+            return Ok(all);
+        }
+        cursor = next;
+    }
+}
+
+fn next_cursor<R: PagedRecord>(cursor: Cursor, records: &[R], page_size: u64) -> Cursor {
+    let (first, last) = match (records.first(), records.last()) {
+        (Some(first), Some(last)) => (first.block_number(), last.block_number()),
+        _ => return cursor,
+    };
+    // restart the query at the last block if the page holds all of its records, which keeps pages
+    // low, as Etherscan only serves the first 10000 records of a query
+    if first < last {
+        let in_last_block = records.iter().rev().take_while(|r| r.block_number() == last).count() as u64;
+        return Cursor { block: last, page: in_last_block / page_size + 1, index: in_last_block % page_size };
+    }
+    if records.len() as u64 == page_size {
+        Cursor { block: cursor.block, page: cursor.page + 1, index: 0 }
+    } else {
+        Cursor { block: cursor.block, page: cursor.page, index: records.len() as u64 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Chain;
+    use crate::testing::FixtureServer;
+
+    const ADDR: &str = "0xddbd2b932c763ba5b1b7ae3b362eac3e8d40121a";
+
+    #[tokio::test]
+    async fn resumes_after_restart() {
+        let server = FixtureServer::start(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), Chain::Mainnet).unwrap();
+        let api = API::new("test").with_base_url(&server.base_url());
+        let path = std::env::temp_dir().join(format!("etherscanio-state-test-{}.json", std::process::id()));
+
+        let state = FileSyncState::open(&path).unwrap();
+        let mut txs = ResumableFetcher::<Transaction>::new(&api, &state, ADDR).with_page_size(2);
+        assert_eq!(txs.next_page().await.unwrap().len(), 2);
+        // not committed, so the page is handed out again
+        assert_eq!(txs.next_page().await.unwrap().len(), 2);
+        txs.commit().unwrap();
+        assert_eq!(txs.cursor(), Cursor { block: 14_923_692, page: 1, index: 1 });
+
+        let state = FileSyncState::open(&path).unwrap();
+        let mut txs = ResumableFetcher::<Transaction>::new(&api, &state, ADDR).with_page_size(2);
+        assert_eq!(txs.cursor(), Cursor { block: 14_923_692, page: 1, index: 1 });
+        assert!(txs.next_page().await.unwrap().is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_a_cursor_per_kind() {
+        let server = FixtureServer::start(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), Chain::Mainnet).unwrap();
+        let api = API::new("test").with_base_url(&server.base_url());
+        let state = MemorySyncState::new();
+        let mut transfers = ResumableFetcher::<ERC721TokenTransferEvent>::new(&api, &state, ADDR);
+        assert!(!transfers.next_page().await.unwrap().is_empty());
+        transfers.commit().unwrap();
+        assert!(state.get(ADDR, RecordKind::ERC721Transfers).is_some());
+        assert!(state.get(ADDR, RecordKind::ERC20Transfers).is_none());
+    }
+
+    #[tokio::test]
+    async fn fetches_range_in_pages() {
+        let server = FixtureServer::start(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"), Chain::Mainnet).unwrap();
+        let api = API::new("test").with_base_url(&server.base_url());
+        let txs: Vec<Transaction> = fetch_range(&api, ADDR, 0, LATEST_BLOCK, 2).await.unwrap();
+        // the second query restarts at the last block and skips its record handed out already
+        assert_eq!(txs.len(), 2);
+        assert_ne!(txs[0].hash(), txs[1].hash());
+    }
+
+    #[test]
+    fn pages_within_one_block() {
+        #[derive(Deserialize, Debug)]
+        struct Record(u64);
+        impl PagedRecord for Record {
+            const KIND: RecordKind = RecordKind::Transactions;
+
+            fn block_number(&self) -> u64 {
+                self.0
+            }
+        }
+        let start = Cursor::default();
+        let full_page = next_cursor(start, &[Record(5), Record(5)], 2);
+        assert_eq!(full_page, Cursor { block: 0, page: 2, index: 0 });
+        let partial_page = next_cursor(full_page, &[Record(5)], 2);
+        assert_eq!(partial_page, Cursor { block: 0, page: 2, index: 1 });
+        assert_eq!(next_cursor(partial_page, &[Record(5), Record(7)], 2), Cursor { block: 7, page: 1, index: 1 });
+        assert_eq!(next_cursor(start, &[Record(5), Record(7), Record(7)], 3), Cursor { block: 7, page: 1, index: 2 });
+    }
+}
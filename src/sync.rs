@@ -0,0 +1,190 @@
+//! Incremental syncing of an account's records which only treats records as final once they are
+//! buried under a number of confirmations.
+//!
+//! Records in the unfinalized tail are handed out as they appear and re-checked on every run. If a
+//! reorg moves one into another block or drops it, a retraction is emitted.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+use super::models::{ERC20TokenTransferEvent, Transaction};
+use super::state::fetch_range;
+use super::{AsyncError, API, LATEST_BLOCK};
+
+const PAGE_SIZE: u64 = 1000;
+
+pub type RecordsFuture<'a, R> = Pin<Box<dyn Future<Output = Result<Vec<R>, AsyncError>> + Send + 'a>>;
+
+/// A record which carries the hash of its block.
+pub trait SyncRecord: Sized {
+    /// Identifies the record across runs, independent of its block.
+    fn id(&self) -> String;
+
+    fn block_number(&self) -> u64;
+
+    fn block_hash(&self) -> &str;
+
+    /// Fetches all of the account's records within the block range, in ascending order.
+    fn fetch<'a>(api: &'a API, address: &'a str, from_block: u64, end_block: u64) -> RecordsFuture<'a, Self>;
+}
+
+impl SyncRecord for Transaction {
+    fn id(&self) -> String {
+        self.hash().into()
+    }
+
+    fn block_number(&self) -> u64 {
+        self.block_number()
+    }
+
+    fn block_hash(&self) -> &str {
+        self.block_hash()
+    }
+
+    fn fetch<'a>(api: &'a API, address: &'a str, from_block: u64, end_block: u64) -> RecordsFuture<'a, Self> {
+        Box::pin(fetch_range(api, address, from_block, end_block, PAGE_SIZE))
+    }
+}
+
+impl SyncRecord for ERC20TokenTransferEvent {
+    fn id(&self) -> String {
+        self.transfer_id()
+    }
+
+    fn block_number(&self) -> u64 {
+        self.block_number()
+    }
+
+    fn block_hash(&self) -> &str {
+        self.block_hash()
+    }
+
+    fn fetch<'a>(api: &'a API, address: &'a str, from_block: u64, end_block: u64) -> RecordsFuture<'a, Self> {
+        Box::pin(fetch_range(api, address, from_block, end_block, PAGE_SIZE))
+    }
+}
+
+#[derive(Debug)]
+pub enum SyncUpdate<R> {
+    /// A new record with fewer confirmations than required, which may still be retracted.
+    Added(R),
+    /// A record with enough confirmations. It may have been added before.
+    Final(R),
+    /// A record added before is no longer in its block. If it was re-mined elsewhere, it is added again.
+    Retracted { id: String, block_number: u64, block_hash: String },
+}
+
+/// Syncs the records of one address and kind, e.g. `IncrementalSync::<Transaction>::new(addr, 12)`.
+pub struct IncrementalSync<R> {
+    address: String,
+    confirmations: u64,
+    // the first block which may still hold unfinalized records
+    next_block: u64,
+    // block number and block hash of the records handed out as `Added`, by id
+    tail: HashMap<String, (u64, String)>,
+    records: PhantomData<R>,
+}
+
+impl<R: SyncRecord> IncrementalSync<R> {
+    pub fn new(address: &str, confirmations: u64) -> IncrementalSync<R> {
+        IncrementalSync::from_block(address, confirmations, 0)
+    }
+
+    /// Starts at `next_block`, e.g. after all records before it have been synced already.
+    pub fn from_block(address: &str, confirmations: u64, next_block: u64) -> IncrementalSync<R> {
+        IncrementalSync { address: address.into(), confirmations, next_block, tail: HashMap::new(), records: PhantomData }
+    }
+
+    /// The first block whose records are not all final yet.
+    pub fn next_block(&self) -> u64 {
+        self.next_block
+    }
+
+    /// Fetches everything from the unfinalized tail on. Updates come in block order, followed by the
+    /// retractions of records which vanished.
+    pub async fn run(&mut self, api: &API) -> Result<Vec<SyncUpdate<R>>, AsyncError> {
+        let head_block = api.block_number().await?;
+        let records = R::fetch(api, &self.address, self.next_block, LATEST_BLOCK).await?;
+        Ok(self.apply(head_block, records))
+    }
+
+    /// Compares the fetched records, which must cover every block from `next_block` on, with the tail.
+    pub fn apply(&mut self, head_block: u64, records: Vec<R>) -> Vec<SyncUpdate<R>> {
+        // a record in this block has `confirmations` confirmations
+        let final_block = (head_block + 1).saturating_sub(self.confirmations.max(1));
+        let mut previous_tail = std::mem::take(&mut self.tail);
+        let mut updates = vec![];
+        for record in records {
+            let id = record.id();
+            if let Some((block_number, block_hash)) = previous_tail.remove(&id) {
+                if block_number != record.block_number() || block_hash != record.block_hash() {
+                    updates.push(SyncUpdate::Retracted { id: id.clone(), block_number, block_hash });
+                } else if record.block_number() > final_block {
+                    self.tail.insert(id, (block_number, block_hash));
+                    continue;
+                }
+            }
+            if record.block_number() <= final_block {
+                updates.push(SyncUpdate::Final(record));
+            } else {
+                self.tail.insert(id, (record.block_number(), record.block_hash().into()));
+                updates.push(SyncUpdate::Added(record));
+            }
+        }
+        // whatever is left of the tail vanished
+        for (id, (block_number, block_hash)) in previous_tail {
+            updates.push(SyncUpdate::Retracted { id, block_number, block_hash });
+        }
+        self.next_block = self.next_block.max(final_block + 1);
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(hash: &str, block_number: u64, block_hash: &str) -> Transaction {
+        serde_json::from_str(&format!(r#"{{"blockNumber":"{}","timeStamp":"1654646411","hash":"{}","nonce":"1","blockHash":"{}","transactionIndex":"0","from":"0x9aa99c23f67c81701c772b106b4f83f6e858dd2e","to":"0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc","value":"0","gas":"21000","gasPrice":"1","isError":"0","txreceipt_status":"1","input":"0x","contractAddress":"","cumulativeGasUsed":"21000","gasUsed":"21000","confirmations":"1"}}"#,
+                                     block_number, hash, block_hash)).unwrap()
+    }
+
+    #[test]
+    fn retracts_reorged_records() {
+        let mut sync = IncrementalSync::<Transaction>::new("0x9aa99c23f67c81701c772b106b4f83f6e858dd2e", 10);
+        let updates = sync.apply(100, vec![tx("0x1", 80, "0xa"), tx("0x2", 95, "0xb"), tx("0x3", 99, "0xc")]);
+        assert!(matches!(updates[0], SyncUpdate::Final(_)));
+        assert!(matches!(updates[1], SyncUpdate::Added(_)));
+        assert!(matches!(updates[2], SyncUpdate::Added(_)));
+        assert_eq!(sync.next_block(), 92);
+
+        // 0x2 is unchanged, 0x3 moved to another block and a new 0x4 showed up
+        let updates = sync.apply(101, vec![tx("0x2", 95, "0xb"), tx("0x3", 100, "0xd"), tx("0x4", 101, "0xe")]);
+        assert_eq!(updates.len(), 3);
+        assert!(matches!(&updates[0], SyncUpdate::Retracted { id, block_hash, .. } if id == "0x3" && block_hash == "0xc"));
+        assert!(matches!(&updates[1], SyncUpdate::Added(tx) if tx.block_number() == 100));
+        assert!(matches!(updates[2], SyncUpdate::Added(_)));
+
+        // 0x4 vanished and 0x2 became final
+        let updates = sync.apply(104, vec![tx("0x2", 95, "0xb"), tx("0x3", 100, "0xd")]);
+        assert_eq!(updates.len(), 2);
+        assert!(matches!(&updates[0], SyncUpdate::Final(tx) if tx.hash() == "0x2"));
+        assert!(matches!(&updates[1], SyncUpdate::Retracted { id, .. } if id == "0x4"));
+        assert_eq!(sync.next_block(), 96);
+    }
+
+    #[test]
+    fn keeps_identical_transfers_of_a_tx_apart() {
+        let transfer = |log_index| -> ERC20TokenTransferEvent {
+            serde_json::from_str(&format!(r#"{{"blockNumber":"99","timeStamp":"1654646411","hash":"0x1","nonce":"1","blockHash":"0xa","from":"0x9aa99c23f67c81701c772b106b4f83f6e858dd2e","contractAddress":"0x514910771af9ca656af840dff83e8264ecf986ca","to":"0xc5102fe9359fd9a28f877a67e36b0f050d81a3cc","value":"1","tokenName":"ChainLink Token","tokenSymbol":"LINK","tokenDecimal":"18","transactionIndex":"0","logIndex":"{}","gas":"60000","gasPrice":"1","gasUsed":"50000","cumulativeGasUsed":"50000","input":"deprecated","confirmations":"1"}}"#,
+                                         log_index)).unwrap()
+        };
+        let mut sync = IncrementalSync::<ERC20TokenTransferEvent>::new("0x9aa99c23f67c81701c772b106b4f83f6e858dd2e", 10);
+        let updates = sync.apply(100, vec![transfer(1), transfer(2)]);
+        assert_eq!(updates.len(), 2);
+        // unchanged, neither is reported again
+        assert!(sync.apply(100, vec![transfer(1), transfer(2)]).is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+//! Offline testing support: a local stub of the Etherscan API which replays recorded responses.
+//!
+//! Fixtures are JSON files holding the normalized request (API key stripped) and the response body.
+//! When `ETHERSCANIO_RECORD` is set, requests without a fixture are forwarded to Etherscan and their
+//! responses are written to the fixture directory, so recording a new fixture is a matter of running
+//! the test once with `ETHERSCANIO_RECORD=1` and a valid `ETHERSCANIO_API_TOKEN`.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use hyper::{Body, Request, Response, Server, StatusCode};
+use hyper::service::{make_service_fn, service_fn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::abi::keccak256;
+use super::cache::normalized_query;
+use super::models::Chain;
+use super::redact_api_key;
+
+pub(crate) const ETHERSCANIO_RECORD: &str = "ETHERSCANIO_RECORD";
+
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    request: String,
+    response: Value,
+}
+
+struct State {
+    dir: PathBuf,
+    fixtures: Mutex<HashMap<String, String>>,
+    record: bool,
+    // where missing fixtures are recorded from
+    upstream_url: &'static str,
+}
+
+/// A stub server replaying the fixtures of a directory. Point an `API` at it with
+/// `API::new(..).with_base_url(&server.base_url())`.
+pub struct FixtureServer {
+    addr: SocketAddr,
+}
+
+impl FixtureServer {
+    /// Loads all fixtures in `dir` and serves them on a random local port. Missing fixtures are
+    /// recorded from the API of `chain`, the fixtures of different chains belong in different
+    /// directories. Must be called from within a tokio runtime, which the server is spawned on.
+    pub fn start<P: AsRef<Path>>(dir: P, chain: Chain) -> std::io::Result<FixtureServer> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut fixtures = HashMap::new();
+        if dir.exists() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                    continue;
+                }
+                let fixture: Fixture = serde_json::from_str(&fs::read_to_string(&path)?)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?;
+                fixtures.insert(fixture.request, fixture.response.to_string());
+            }
+        }
+        let record = std::env::var(ETHERSCANIO_RECORD).is_ok();
+        let state = Arc::new(State { dir, fixtures: Mutex::new(fixtures), record, upstream_url: chain.base_url() });
+
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| serve(state.clone(), req)))
+            }
+        });
+        // io::Error::other would need Rust 1.74
+        #[allow(clippy::io_other_error)]
+        let server = Server::try_bind(&([127, 0, 0, 1], 0).into())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
+            .serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        Ok(FixtureServer { addr })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api", self.addr)
+    }
+}
+
+async fn serve(state: Arc<State>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let query = req.uri().query().unwrap_or("").to_string();
+    let key = normalized_query(&query);
+    let fixture = state.fixtures.lock().unwrap().get(&key).cloned();
+    let body = match fixture {
+        Some(body) => body,
+        None if state.record => match record(&state, &query, &key).await {
+            Ok(body) => body,
+            Err(e) => return Ok(error_response(StatusCode::BAD_GATEWAY, &format!("recording {} failed: {}", key, e))),
+        },
+        None => return Ok(error_response(StatusCode::NOT_FOUND, &format!("no fixture for {}, record it with {}=1", key, ETHERSCANIO_RECORD))),
+    };
+    Ok(Response::new(Body::from(body)))
+}
+
+async fn record(state: &State, query: &str, key: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    // reqwest errors carry the URL, which holds the API key
+    let body = reqwest::get(&format!("{}?{}", state.upstream_url, query)).await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| redact_api_key(&e.to_string()))?
+        .text().await
+        .map_err(|e| redact_api_key(&e.to_string()))?;
+    let fixture = Fixture { request: key.into(), response: serde_json::from_str(&body)? };
+    fs::create_dir_all(&state.dir)?;
+    fs::write(state.dir.join(fixture_file_name(key)), serde_json::to_string_pretty(&fixture)?)?;
+    state.fixtures.lock().unwrap().insert(key.into(), body.clone());
+    Ok(body)
+}
+
+/// E.g. `account_balance_1f2e3d4c.json`.
+fn fixture_file_name(key: &str) -> String {
+    let params: HashMap<_, _> = key.split('&').filter_map(|p| {
+        let mut kv = p.splitn(2, '=');
+        Some((kv.next()?, kv.next()?))
+    }).collect();
+    let hash = hex::encode(&keccak256(key.as_bytes())[..4]);
+    format!("{}_{}_{}.json", params.get("module").unwrap_or(&""), params.get("action").unwrap_or(&""), hash)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
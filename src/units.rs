@@ -0,0 +1,206 @@
+use std::fmt;
+
+use primitive_types::U256;
+
+pub const WEI_DECIMALS: u32 = 0;
+pub const GWEI_DECIMALS: u32 = 9;
+pub const ETHER_DECIMALS: u32 = 18;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseUnitsError {
+    Invalid(String),
+    TooManyDecimals { value: String, decimals: u32 },
+    Overflow(String),
+}
+
+impl fmt::Display for ParseUnitsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseUnitsError::Invalid(value) => write!(f, "invalid decimal '{}'", value),
+            ParseUnitsError::TooManyDecimals { value, decimals } => write!(f, "decimal '{}' has more than {} fractional digits", value, decimals),
+            ParseUnitsError::Overflow(value) => write!(f, "decimal '{}' is out of range", value),
+        }
+    }
+}
+
+impl std::error::Error for ParseUnitsError {}
+
+/// Parses a decimal string such as `"1.5"` into an integer amount of the smallest unit, scaled by `10^decimals`.
+/// Fails instead of rounding if the input has more fractional digits than `decimals`.
+pub fn parse_units(s: &str, decimals: u32) -> Result<u128, ParseUnitsError> {
+    let s = s.trim();
+    let (int_part, frac_part) = match s.find('.') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(ParseUnitsError::Invalid(s.into()));
+    }
+    if !int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(ParseUnitsError::Invalid(s.into()));
+    }
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.len() > decimals as usize {
+        return Err(ParseUnitsError::TooManyDecimals { value: s.into(), decimals });
+    }
+    let mut digits = String::with_capacity(int_part.len() + decimals as usize);
+    digits.push_str(int_part);
+    digits.push_str(frac_part);
+    digits.push_str(&"0".repeat(decimals as usize - frac_part.len()));
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    digits.parse().map_err(|_| ParseUnitsError::Overflow(s.into()))
+}
+
+/// Formats an integer amount of the smallest unit as a decimal string without trailing zeros.
+pub fn format_units(value: u128, decimals: u32) -> String {
+    format_digits(value.to_string(), decimals)
+}
+
+/// Like `format_units`, for amounts such as fees which may exceed `u128`.
+pub fn format_units_u256(value: U256, decimals: u32) -> String {
+    format_digits(value.to_string(), decimals)
+}
+
+fn format_digits(mut digits: String, decimals: u32) -> String {
+    let decimals = decimals as usize;
+    if digits.len() <= decimals {
+        digits = format!("{}{}", "0".repeat(decimals + 1 - digits.len()), digits);
+    }
+    let (int_part, frac_part) = digits.split_at(digits.len() - decimals);
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.is_empty() {
+        int_part.to_string()
+    } else {
+        format!("{}.{}", int_part, frac_part)
+    }
+}
+
+pub fn parse_ether(s: &str) -> Result<u128, ParseUnitsError> {
+    parse_units(s, ETHER_DECIMALS)
+}
+
+pub fn parse_gwei(s: &str) -> Result<u128, ParseUnitsError> {
+    parse_units(s, GWEI_DECIMALS)
+}
+
+pub fn format_ether(wei: u128) -> String {
+    format_units(wei, ETHER_DECIMALS)
+}
+
+pub fn format_gwei(wei: u128) -> String {
+    format_units(wei, GWEI_DECIMALS)
+}
+
+/// An amount of ether, held in wei. Displays as e.g. `1.5 ETH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EtherAmount(pub u128);
+
+impl EtherAmount {
+    pub fn wei(&self) -> u128 {
+        self.0
+    }
+}
+
+impl fmt::Display for EtherAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ETH", format_ether(self.0))
+    }
+}
+
+/// An amount of gas price, held in wei. Displays as e.g. `12.5 gwei`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GweiAmount(pub u128);
+
+impl GweiAmount {
+    pub fn wei(&self) -> u128 {
+        self.0
+    }
+}
+
+impl fmt::Display for GweiAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} gwei", format_gwei(self.0))
+    }
+}
+
+/// An amount of tokens in raw token units together with the token's decimals and symbol.
+/// Displays as e.g. `12.34 LINK`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenAmount {
+    value: u128,
+    decimals: u32,
+    symbol: String,
+}
+
+impl TokenAmount {
+    pub fn new(value: u128, decimals: u32, symbol: &str) -> TokenAmount {
+        TokenAmount { value, decimals, symbol: symbol.into() }
+    }
+
+    pub fn parse(s: &str, decimals: u32, symbol: &str) -> Result<TokenAmount, ParseUnitsError> {
+        Ok(TokenAmount::new(parse_units(s, decimals)?, decimals, symbol))
+    }
+
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// The amount as a decimal string without the symbol.
+    pub fn to_decimal_string(&self) -> String {
+        format_units(self.value, self.decimals)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.symbol.is_empty() {
+            return write!(f, "{}", self.to_decimal_string());
+        }
+        write!(f, "{} {}", self.to_decimal_string(), self.symbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_gwei("12"), Ok(12_000_000_000));
+        assert_eq!(parse_gwei("12.345678912"), Ok(12_345_678_912));
+        assert_eq!(parse_gwei("0.5"), Ok(500_000_000));
+        assert_eq!(parse_gwei(".5"), Ok(500_000_000));
+        assert_eq!(parse_ether("1.5"), Ok(1_500_000_000_000_000_000));
+        assert_eq!(parse_units("1.100", 2), Ok(110));
+        assert_eq!(parse_units("0", 18), Ok(0));
+        assert!(parse_gwei("1.0000000001").is_err());
+        assert!(parse_gwei("").is_err());
+        assert!(parse_gwei("1,5").is_err());
+        assert!(parse_units("1", 39).is_err());
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_ether(1_500_000_000_000_000_000), "1.5");
+        assert_eq!(format_ether(1), "0.000000000000000001");
+        assert_eq!(format_ether(0), "0");
+        assert_eq!(format_gwei(12_000_000_000), "12");
+        assert_eq!(format_units(u128::MAX, 40), format!("0.0{}", u128::MAX));
+        assert_eq!(format_units_u256(U256::from(u128::MAX) * 10, 18), format_units(u128::MAX, 17));
+        assert_eq!(EtherAmount(2_000_000_000_000_000_000).to_string(), "2 ETH");
+        assert_eq!(GweiAmount(12_500_000_000).to_string(), "12.5 gwei");
+        assert_eq!(TokenAmount::new(1_234_500, 6, "USDC").to_string(), "1.2345 USDC");
+        assert_eq!(TokenAmount::parse("1.2345", 6, "USDC").unwrap().value(), 1_234_500);
+    }
+}
@@ -0,0 +1,212 @@
+//! Polls Etherscan for new transactions and ERC20 transfers of a set of addresses.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use futures::stream::{self, Stream};
+use tokio::time::{delay_until, Instant};
+
+use super::models::{ERC20TokenTransferEvent, Transaction};
+use super::{AsyncError, API, LATEST_BLOCK};
+
+/// A new record concerning a watched address.
+#[derive(Debug)]
+pub enum WatchEvent {
+    Transaction { address: String, tx: Transaction },
+    ERC20Transfer { address: String, transfer: ERC20TokenTransferEvent },
+}
+
+impl WatchEvent {
+    /// The watched address the record concerns.
+    pub fn address(&self) -> &str {
+        match self {
+            WatchEvent::Transaction { address, .. } => address,
+            WatchEvent::ERC20Transfer { address, .. } => address,
+        }
+    }
+
+    pub fn block_number(&self) -> u64 {
+        match self {
+            WatchEvent::Transaction { tx, .. } => tx.block_number(),
+            WatchEvent::ERC20Transfer { transfer, .. } => transfer.block_number(),
+        }
+    }
+
+    pub fn hash(&self) -> &str {
+        match self {
+            WatchEvent::Transaction { tx, .. } => tx.hash(),
+            WatchEvent::ERC20Transfer { transfer, .. } => transfer.hash(),
+        }
+    }
+
+    fn key(&self) -> String {
+        match self {
+            WatchEvent::Transaction { address, tx } => format!("{}:tx:{}", address, tx.hash()),
+            WatchEvent::ERC20Transfer { address, transfer } => format!("{}:erc20:{}", address, transfer.transfer_id()),
+        }
+    }
+}
+
+struct WatchedAddress {
+    address: String,
+    from_block: u64,
+    last_seen_block: Option<u64>,
+}
+
+impl WatchedAddress {
+    fn rescan_from(&self, reorg_depth: u64) -> u64 {
+        match self.last_seen_block {
+            Some(block) => block.saturating_sub(reorg_depth).max(self.from_block),
+            None => self.from_block,
+        }
+    }
+}
+
+/// Watches addresses by polling `txs_on_account_from_to` and `erc20_transfers_on_account_from_to`.
+///
+/// Every poll re-scans the last `reorg_depth` blocks before the last seen block, so records which
+/// were re-mined in another block after a reorg are still picked up. Records are emitted once only.
+pub struct AddressWatcher<'a> {
+    api: &'a API,
+    interval: Duration,
+    reorg_depth: u64,
+    addresses: Vec<WatchedAddress>,
+    // keys of emitted events by block, forgotten once the block is out of the re-scanned range
+    seen: HashMap<String, u64>,
+    queue: VecDeque<WatchEvent>,
+    last_poll: Option<Instant>,
+}
+
+impl<'a> AddressWatcher<'a> {
+    pub fn new(api: &'a API) -> AddressWatcher<'a> {
+        AddressWatcher {
+            api,
+            interval: Duration::from_secs(15),
+            reorg_depth: 12,
+            addresses: vec![],
+            seen: HashMap::new(),
+            queue: VecDeque::new(),
+            last_poll: None,
+        }
+    }
+
+    /// The time between two polls, 15 seconds by default. Stretched if the API's rate limit doesn't
+    /// allow polling all addresses that often.
+    pub fn with_interval(mut self, interval: Duration) -> AddressWatcher<'a> {
+        self.interval = interval;
+        self
+    }
+
+    /// The number of blocks re-scanned on every poll, 12 by default.
+    pub fn with_reorg_depth(mut self, reorg_depth: u64) -> AddressWatcher<'a> {
+        self.reorg_depth = reorg_depth;
+        self
+    }
+
+    /// Watches the address for records from `from_block` on.
+    pub fn watch(mut self, address: &str, from_block: u64) -> AddressWatcher<'a> {
+        self.addresses.push(WatchedAddress { address: address.to_lowercase(), from_block, last_seen_block: None });
+        self
+    }
+
+    /// The last seen block of a watched address.
+    pub fn last_seen_block(&self, address: &str) -> Option<u64> {
+        self.addresses.iter().find(|a| a.address.eq_ignore_ascii_case(address)).and_then(|a| a.last_seen_block)
+    }
+
+    fn poll_interval(&self) -> Duration {
+        // two requests per address and poll
+        self.interval.max(self.api.request_interval() * 2 * self.addresses.len() as u32)
+    }
+
+    /// Fetches the records which are new since the last poll, ordered by block.
+    pub async fn poll(&mut self) -> Result<Vec<WatchEvent>, AsyncError> {
+        self.last_poll = Some(Instant::now());
+        // all addresses are fetched before anything is marked as seen, so a failed poll loses nothing
+        let mut fetched = Vec::with_capacity(self.addresses.len());
+        for watched in &self.addresses {
+            let from_block = watched.rescan_from(self.reorg_depth);
+            let txs = self.api.txs_on_account_from_to(&watched.address, from_block, LATEST_BLOCK).await?;
+            let transfers = self.api.erc20_transfers_on_account_from_to(&watched.address, from_block, LATEST_BLOCK).await?;
+            fetched.push((txs, transfers));
+        }
+
+        let mut events = vec![];
+        for (watched, (txs, transfers)) in self.addresses.iter_mut().zip(fetched) {
+            let address = &watched.address;
+            let found = txs.into_iter().map(|tx| WatchEvent::Transaction { address: address.clone(), tx })
+                .chain(transfers.into_iter().map(|transfer| WatchEvent::ERC20Transfer { address: address.clone(), transfer }));
+            let mut latest_block = watched.last_seen_block;
+            for event in found {
+                latest_block = latest_block.max(Some(event.block_number()));
+                if self.seen.insert(event.key(), event.block_number()).is_none() {
+                    events.push(event);
+                }
+            }
+            watched.last_seen_block = latest_block;
+        }
+        let oldest_rescanned = self.addresses.iter().map(|a| a.rescan_from(self.reorg_depth)).min().unwrap_or(0);
+        self.seen.retain(|_, block| *block >= oldest_rescanned);
+        events.sort_by_key(WatchEvent::block_number);
+        Ok(events)
+    }
+
+    /// Polls forever. A failed poll yields its error and is retried after the interval.
+    pub fn into_stream(self) -> impl Stream<Item = Result<WatchEvent, AsyncError>> + 'a {
+        stream::unfold(self, |mut watcher| async move {
+            loop {
+                if let Some(event) = watcher.queue.pop_front() {
+                    return Some((Ok(event), watcher));
+                }
+                if let Some(last_poll) = watcher.last_poll {
+                    delay_until(last_poll + watcher.poll_interval()).await;
+                }
+                match watcher.poll().await {
+                    Ok(events) => watcher.queue.extend(events),
+                    Err(e) => return Some((Err(e), watcher)),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::tests::test_api;
+
+    const ADDR: &str = "0xddbd2b932c763ba5b1b7ae3b362eac3e8d40121a";
+
+    #[tokio::test]
+    async fn polls_new_records_once() {
+        let api = test_api();
+        let mut watcher = AddressWatcher::new(&api).watch(ADDR, 14_923_678);
+        let events = watcher.poll().await.unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].block_number(), 14_923_678);
+        assert_eq!(watcher.last_seen_block(ADDR), Some(14_923_692));
+        // the re-scanned blocks hold nothing new
+        assert!(watcher.poll().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_records_of_a_failed_poll_unseen() {
+        let api = test_api();
+        // there are no fixtures for the second address
+        let mut watcher = AddressWatcher::new(&api).watch(ADDR, 14_923_678).watch("0x0000000000000000000000000000000000000001", 0);
+        assert!(watcher.poll().await.is_err());
+        assert!(watcher.seen.is_empty());
+        assert_eq!(watcher.last_seen_block(ADDR), None);
+    }
+
+    #[tokio::test]
+    async fn streams_events() {
+        let api = test_api().with_rate_limit(5);
+        let watcher = AddressWatcher::new(&api).watch(ADDR, 14_923_678);
+        let events: Vec<_> = watcher.into_stream().take(4).collect().await;
+        assert!(events.iter().all(Result::is_ok));
+        assert!(matches!(events[3], Ok(WatchEvent::ERC20Transfer { .. })));
+    }
+}